@@ -1,10 +1,30 @@
-use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
 use crate::error::MarkitdownError;
+use crate::model::{
+    ConversionOptions, ConversionWarning, DocumentConverter, DocumentConverterResult, WarningKind,
+};
+use crate::table::escape_cell;
 use csv::ReaderBuilder;
-use std::fs::File;
+use std::collections::HashSet;
 
 pub struct CsvConverter;
 
+const CSV_EXTENSIONS: [&str; 3] = [".csv", ".tsv", ".tab"];
+
+/// Delimiters tried by the dialect sniffer, in order of preference on ties.
+const DELIMITERS: [u8; 4] = [b',', b';', b'\t', b'|'];
+
+/// Number of records inspected when sniffing the dialect.
+const SNIFF_RECORDS: usize = 50;
+
+/// Field separation and quoting of a delimited text file.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Dialect {
+    delimiter: u8,
+    quote: u8,
+    /// `None` when quotes inside quoted fields are escaped by doubling them.
+    escape: Option<u8>,
+}
+
 impl DocumentConverter for CsvConverter {
     fn convert(
         &self,
@@ -13,37 +33,17 @@ impl DocumentConverter for CsvConverter {
     ) -> Result<DocumentConverterResult, MarkitdownError> {
         if let Some(opts) = &args {
             if let Some(ext) = &opts.file_extension {
-                if ext != ".csv" {
-                    return Err(MarkitdownError::InvalidFile(
-                        format!("Expected .csv file, got {}", ext)
-                    ));
+                if !CSV_EXTENSIONS.contains(&ext.as_str()) {
+                    return Err(MarkitdownError::InvalidFile(format!(
+                        "Expected .csv, .tsv or .tab file, got {}",
+                        ext
+                    )));
                 }
             }
         }
 
-        let mut markdown = String::new();
-        let file = File::open(local_path)?;
-        let mut rdr = ReaderBuilder::new().has_headers(false).from_reader(file);
-        for result in rdr.records() {
-            match result {
-                Ok(record) => {
-                    let rc: String = record
-                        .iter()
-                        .map(|s| s.as_ref())
-                        .collect::<Vec<&str>>()
-                        .join(",");
-                    markdown.push_str(&rc);
-                    markdown.push_str("\n");
-                }
-                Err(err) => {
-                    markdown.push_str(&format!("{:?}\n", err));
-                }
-            }
-        }
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content: markdown,
-        })
+        let bytes = std::fs::read(local_path)?;
+        csv_to_markdown(&bytes, &args)
     }
 
     fn convert_bytes(
@@ -53,34 +53,336 @@ impl DocumentConverter for CsvConverter {
     ) -> Result<DocumentConverterResult, MarkitdownError> {
         if let Some(opts) = &args {
             if let Some(ext) = &opts.file_extension {
-                if ext != ".csv" {
-                    return Err(MarkitdownError::InvalidFile(
-                        format!("Expected .csv file, got {}", ext)
-                    ));
+                if !CSV_EXTENSIONS.contains(&ext.as_str()) {
+                    return Err(MarkitdownError::InvalidFile(format!(
+                        "Expected .csv, .tsv or .tab file, got {}",
+                        ext
+                    )));
+                }
+            }
+        }
+
+        csv_to_markdown(bytes, &args)
+    }
+}
+
+fn csv_to_markdown(
+    bytes: &[u8],
+    args: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    let options = args.clone().unwrap_or_default();
+    let dialect = resolve_dialect(bytes, &options);
+    let mut rdr = ReaderBuilder::new()
+        .has_headers(false)
+        .flexible(!options.csv_strict)
+        .delimiter(dialect.delimiter)
+        .quote(dialect.quote)
+        .escape(dialect.escape)
+        .double_quote(dialect.escape.is_none())
+        .from_reader(bytes);
+
+    // Room for a header row, which is never counted against the row cap.
+    let limit = options.csv_max_rows.map(|max_rows| max_rows + 1);
+    let mut rows: Vec<Vec<String>> = Vec::new();
+    let mut hidden_rows = 0;
+    let mut warnings = Vec::new();
+    let mut width = None;
+    for result in rdr.byte_records() {
+        let record = match result {
+            Ok(record) => record,
+            Err(err) if options.csv_strict => {
+                return Err(MarkitdownError::Malformed(err.to_string()));
+            }
+            Err(err) => {
+                warnings.push(ConversionWarning {
+                    line: err.position().map(|pos| pos.line()),
+                    kind: WarningKind::Malformed,
+                    message: err.to_string(),
+                });
+                // An I/O error cannot be recovered from; anything else only
+                // affects the current record.
+                if matches!(err.kind(), csv::ErrorKind::Io(_)) {
+                    break;
                 }
+                continue;
             }
+        };
+        let line = record.position().map(|pos| pos.line());
+
+        let expected = *width.get_or_insert(record.len());
+        if record.len() != expected {
+            warnings.push(ConversionWarning {
+                line,
+                kind: WarningKind::UnequalLengths,
+                message: format!("expected {} fields, found {}", expected, record.len()),
+            });
         }
-        let mut markdown = String::new();
-        let mut rdr = ReaderBuilder::new().has_headers(false).from_reader(bytes);
-        for result in rdr.records() {
-            match result {
-                Ok(record) => {
-                    let rc: String = record
-                        .iter()
-                        .map(|s| s.as_ref())
-                        .collect::<Vec<&str>>()
-                        .join(",");
-                    markdown.push_str(&rc);
-                    markdown.push_str("\n");
+
+        let mut row = Vec::with_capacity(record.len());
+        for field in record.iter() {
+            match std::str::from_utf8(field) {
+                Ok(field) => row.push(field.to_string()),
+                Err(err) if options.csv_strict => {
+                    return Err(MarkitdownError::Malformed(format!(
+                        "CSV record on line {}: {}",
+                        line.unwrap_or_default(),
+                        err
+                    )));
                 }
                 Err(err) => {
-                    markdown.push_str(&format!("{:?}\n", err));
+                    warnings.push(ConversionWarning {
+                        line,
+                        kind: WarningKind::InvalidUtf8,
+                        message: err.to_string(),
+                    });
+                    row.push(String::from_utf8_lossy(field).into_owned());
                 }
             }
         }
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content: markdown,
-        })
+
+        if limit.is_some_and(|limit| rows.len() >= limit) {
+            hidden_rows += 1;
+        } else {
+            rows.push(row);
+        }
+    }
+
+    // The cap counts data rows, so the first record only takes the extra
+    // slot when it turns out to be a header.
+    let has_header = options
+        .csv_has_header
+        .unwrap_or_else(|| detect_header(&rows));
+    if let Some(max_rows) = options.csv_max_rows.filter(|_| !has_header) {
+        if rows.len() > max_rows {
+            hidden_rows += rows.len() - max_rows;
+            rows.truncate(max_rows);
+        }
+    }
+
+    let mut markdown = rows_to_markdown(rows, has_header);
+    if hidden_rows > 0 {
+        let noun = if hidden_rows == 1 { "row" } else { "rows" };
+        markdown.push_str(&format!("\n… {} more {}\n", hidden_rows, noun));
+    }
+
+    Ok(DocumentConverterResult {
+        title: None,
+        text_content: markdown,
+        warnings,
+        ..Default::default()
+    })
+}
+
+/// Sniffs the dialect of `bytes`, letting explicit options take precedence.
+fn resolve_dialect(bytes: &[u8], options: &ConversionOptions) -> Dialect {
+    let is_tab_file = matches!(
+        options.file_extension.as_deref(),
+        Some(".tsv") | Some(".tab")
+    );
+    let sample = &bytes[..bytes.len().min(64 * 1024)];
+    let quote = options.csv_quote.unwrap_or_else(|| sniff_quote(sample));
+    let escape = match options.csv_escape {
+        Some(escape) => Some(escape),
+        None => sniff_escape(sample, quote),
+    };
+    let delimiter = options.csv_delimiter.unwrap_or_else(|| {
+        let default = if is_tab_file { b'\t' } else { b',' };
+        sniff_delimiter(sample, quote, escape, sample.len() < bytes.len()).unwrap_or(default)
+    });
+    Dialect {
+        delimiter,
+        quote,
+        escape,
+    }
+}
+
+/// Picks the delimiter that splits the sample into the most consistent
+/// number of fields, preferring wider records when equally consistent.
+fn sniff_delimiter(sample: &[u8], quote: u8, escape: Option<u8>, truncated: bool) -> Option<u8> {
+    // (delimiter, records matching the mode, records read, mode)
+    let mut best: Option<(u8, usize, usize, usize)> = None;
+    for delimiter in DELIMITERS {
+        let mut rdr = ReaderBuilder::new()
+            .has_headers(false)
+            .flexible(true)
+            .delimiter(delimiter)
+            .quote(quote)
+            .escape(escape)
+            .double_quote(escape.is_none())
+            .from_reader(sample);
+        let mut counts: Vec<usize> = rdr
+            .records()
+            .take(SNIFF_RECORDS)
+            .map_while(Result::ok)
+            .map(|record| record.len())
+            .collect();
+        // The last record of a cut-off sample is likely incomplete.
+        if truncated && counts.len() > 1 {
+            counts.pop();
+        }
+
+        let Some(mode) = most_common(&counts).filter(|&mode| mode > 1) else {
+            continue;
+        };
+        let consistent = counts.iter().filter(|&&count| count == mode).count();
+        let total = counts.len();
+        let better = best.is_none_or(|(_, best_consistent, best_total, best_mode)| {
+            let (ratio, best_ratio) = (consistent * best_total, best_consistent * total);
+            ratio > best_ratio || (ratio == best_ratio && mode > best_mode)
+        });
+        if better {
+            best = Some((delimiter, consistent, total, mode));
+        }
+    }
+    best.map(|(delimiter, ..)| delimiter)
+}
+
+fn most_common(counts: &[usize]) -> Option<usize> {
+    let mut frequencies: Vec<(usize, usize)> = Vec::new();
+    for &count in counts {
+        match frequencies.iter_mut().find(|(value, _)| *value == count) {
+            Some((_, frequency)) => *frequency += 1,
+            None => frequencies.push((count, 1)),
+        }
+    }
+    // `max_by_key` keeps the last maximum, so reverse to favour the first seen.
+    frequencies
+        .into_iter()
+        .rev()
+        .max_by_key(|&(_, frequency)| frequency)
+        .map(|(value, _)| value)
+}
+
+/// Chooses between double and single quotes by counting how often each opens
+/// a field, i.e. follows a line start or a candidate delimiter.
+fn sniff_quote(sample: &[u8]) -> u8 {
+    let opens = |quote: u8| {
+        sample
+            .iter()
+            .enumerate()
+            .filter(|&(i, &byte)| {
+                byte == quote
+                    && (i == 0
+                        || matches!(sample[i - 1], b'\n' | b'\r')
+                        || DELIMITERS.contains(&sample[i - 1]))
+            })
+            .count()
+    };
+    if opens(b'\'') > opens(b'"') {
+        b'\''
+    } else {
+        b'"'
+    }
+}
+
+/// Detects backslash escaped quotes (`\"`) used instead of doubled ones.
+fn sniff_escape(sample: &[u8], quote: u8) -> Option<u8> {
+    let backslashed = sample
+        .windows(2)
+        .filter(|pair| pair[0] == b'\\' && pair[1] == quote)
+        .count();
+    let doubled = sample
+        .windows(3)
+        .filter(|triple| triple[0] != b'\\' && triple[1] == quote && triple[2] == quote)
+        .count();
+    (backslashed > 0 && doubled == 0).then_some(b'\\')
+}
+
+/// Renders records as a GFM table. Numeric columns are right aligned and a
+/// `Column N` header is generated when the first record is data.
+fn rows_to_markdown(mut rows: Vec<Vec<String>>, has_header: bool) -> String {
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    for row in rows.iter_mut() {
+        row.resize(width, String::new());
+    }
+    // Trailing delimiters leave columns without any content.
+    let used: Vec<usize> = (0..width)
+        .filter(|&col| rows.iter().any(|row| !row[col].trim().is_empty()))
+        .collect();
+    let rows: Vec<Vec<String>> = rows
+        .into_iter()
+        .map(|row| used.iter().map(|&col| row[col].clone()).collect())
+        .collect();
+    if rows.is_empty() || used.is_empty() {
+        return String::new();
+    }
+
+    let (header, data) = if has_header {
+        (rows[0].clone(), &rows[1..])
+    } else {
+        let header = (1..=used.len()).map(|i| format!("Column {}", i)).collect();
+        (header, &rows[..])
+    };
+
+    let mut markdown = String::from("|");
+    for cell in &header {
+        markdown.push_str(&format!(" {} |", escape_cell(cell.trim())));
+    }
+    markdown.push_str("\n|");
+    for col in 0..header.len() {
+        if is_numeric_column(data, col) {
+            markdown.push_str(" ---: |");
+        } else {
+            markdown.push_str(" --- |");
+        }
     }
+    markdown.push('\n');
+
+    for row in data {
+        markdown.push('|');
+        for cell in row {
+            markdown.push_str(&format!(" {} |", escape_cell(cell.trim())));
+        }
+        markdown.push('\n');
+    }
+    markdown
+}
+
+/// Treats the first record as a header when it holds only text labels and
+/// either sits above a numeric column or its labels are all distinct.
+fn detect_header(rows: &[Vec<String>]) -> bool {
+    let Some((first, data)) = rows.split_first() else {
+        return false;
+    };
+    if first.iter().any(|cell| is_numeric(cell)) {
+        return false;
+    }
+    let labels: Vec<&str> = first
+        .iter()
+        .map(|cell| cell.trim())
+        .filter(|cell| !cell.is_empty())
+        .collect();
+    if labels.is_empty() {
+        return false;
+    }
+    if data.is_empty() {
+        return true;
+    }
+    let numeric_below =
+        (0..first.len()).any(|col| !first[col].trim().is_empty() && is_numeric_column(data, col));
+    numeric_below || labels.iter().collect::<HashSet<_>>().len() == labels.len()
+}
+
+fn is_numeric_column(rows: &[Vec<String>], col: usize) -> bool {
+    let mut values = rows
+        .iter()
+        .filter_map(|row| row.get(col))
+        .filter(|cell| !cell.trim().is_empty())
+        .peekable();
+    values.peek().is_some() && values.all(|cell| is_numeric(cell))
+}
+
+/// Accepts plain numbers as well as thousands separators, currency symbols
+/// and percentages, e.g. `$1,200.50` or `12%`.
+fn is_numeric(value: &str) -> bool {
+    let value = value
+        .trim()
+        .trim_start_matches(['$', '€', '£', '¥'])
+        .trim_end_matches('%')
+        .replace(',', "");
+    value.chars().any(|c| c.is_ascii_digit())
+        && value
+            .chars()
+            .all(|c| c.is_ascii_digit() || matches!(c, '.' | '-' | '+' | 'e' | 'E'))
+        && value.parse::<f64>().is_ok()
 }
@@ -91,6 +91,7 @@ impl DocumentConverter for DocxConverter {
         Ok(DocumentConverterResult {
             title: None,
             text_content: markdown,
+            ..Default::default()
         })
     }
 
@@ -175,6 +176,7 @@ impl DocumentConverter for DocxConverter {
         Ok(DocumentConverterResult {
             title: None,
             text_content: markdown,
+            ..Default::default()
         })
     }
 }
@@ -10,6 +10,17 @@ pub enum MarkitdownError {
     InvalidFile(String),
     ParseError(String),
     NetworkError(String),
+    /// The document is encrypted and no password was given.
+    PasswordRequired(String),
+    /// The given password does not open the document.
+    WrongPassword(String),
+    /// The document is encrypted with a method that can't be decrypted.
+    UnsupportedEncryption(String),
+    /// A malformed record in a document converted with strict parsing.
+    Malformed(String),
+    /// An option that does not fit the document, such as an unknown
+    /// spreadsheet range.
+    InvalidOption(String),
 }
 
 impl fmt::Display for MarkitdownError {
@@ -45,6 +56,31 @@ impl fmt::Display for MarkitdownError {
                 "Network error: {} - Please check your internet connection",
                 msg
             ),
+            MarkitdownError::PasswordRequired(msg) => write!(
+                f,
+                "Password required: {} - Please provide the document password",
+                msg
+            ),
+            MarkitdownError::WrongPassword(msg) => write!(
+                f,
+                "Wrong password: {} - Please check the password and try again",
+                msg
+            ),
+            MarkitdownError::UnsupportedEncryption(msg) => write!(
+                f,
+                "Unsupported encryption: {} - Please save an unencrypted copy of the document",
+                msg
+            ),
+            MarkitdownError::Malformed(msg) => write!(
+                f,
+                "Malformed document: {} - Turn off strict parsing to skip malformed records",
+                msg
+            ),
+            MarkitdownError::InvalidOption(msg) => write!(
+                f,
+                "Invalid option: {} - Please check the conversion options",
+                msg
+            ),
         }
     }
 }
@@ -62,4 +98,3 @@ impl From<zip::result::ZipError> for MarkitdownError {
         MarkitdownError::Zip(error.to_string())
     }
 }
-
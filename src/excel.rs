@@ -1,11 +1,23 @@
-use calamine::{open_workbook, Reader, Xlsx};
-use std::{io::Cursor, path::Path};
+use calamine::{
+    open_workbook_auto_from_rs, open_workbook_from_rs, Data, Dimensions, Ods, Range, Reader,
+    Sheets, Xls, Xlsb, Xlsx,
+};
+use chrono::{Datelike, NaiveDate, NaiveDateTime, Timelike};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Decoder;
+use std::collections::HashMap;
+use std::io::{Cursor, Read, Seek};
+use zip::ZipArchive;
 
 use crate::error::MarkitdownError;
-use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult, MergedCellMode};
+use crate::table::rows_to_markdown;
 
 pub struct ExcelConverter;
 
+/// Workbook extensions readable by calamine.
+const EXCEL_EXTENSIONS: [&str; 7] = [".xlsx", ".xlsm", ".xlam", ".xlsb", ".xls", ".xla", ".ods"];
+
 impl DocumentConverter for ExcelConverter {
     fn convert(
         &self,
@@ -14,115 +26,1349 @@ impl DocumentConverter for ExcelConverter {
     ) -> Result<DocumentConverterResult, MarkitdownError> {
         if let Some(opts) = &args {
             if let Some(ext) = &opts.file_extension {
-                if ext != ".xlsx" && ext != ".xls" {
-                    return Err(MarkitdownError::InvalidFile(
-                        format!("Expected .xlsx or .xls file, got {}", ext)
-                    ));
+                if !EXCEL_EXTENSIONS.contains(&ext.as_str()) {
+                    return Err(MarkitdownError::InvalidFile(format!(
+                        "Expected .xlsx, .xlsm, .xlsb, .xls or .ods file, got {}",
+                        ext
+                    )));
                 }
             }
         }
 
-        let path = Path::new(local_path);
-        println!("Opening file: {:#?}", path);
-        let mut workbook: Xlsx<_> = open_workbook(path)
-            .map_err(|e| MarkitdownError::ParseError(format!("Failed to open Excel file: {}", e)))?;
-        let mut markdown = String::new();
+        let bytes = std::fs::read(local_path)?;
+        workbook_to_markdown(&bytes, &args)
+    }
 
-        if let Some(Ok(range)) = workbook.worksheet_range_at(0) {
-            let rows: Vec<Vec<String>> = range
-                .rows()
-                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
-                .collect();
+    fn convert_bytes(
+        &self,
+        bytes: &[u8],
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        if let Some(opts) = &args {
+            if let Some(ext) = &opts.file_extension {
+                if !EXCEL_EXTENSIONS.contains(&ext.as_str()) {
+                    return Err(MarkitdownError::InvalidFile(format!(
+                        "Expected .xlsx, .xlsm, .xlsb, .xls or .ods file, got {}",
+                        ext
+                    )));
+                }
+            }
+        }
 
-            if rows.is_empty() {
-                return Ok(DocumentConverterResult {
-                    title: None,
-                    text_content: String::new(),
-                });
+        workbook_to_markdown(bytes, &args)
+    }
+}
+
+/// Opens a workbook with the reader matching the requested extension, falling
+/// back to content sniffing. The fallback matters because `infer` reports
+/// `.xlsb` files as `.xlsx` and cannot tell every `.xls` variant apart.
+fn open_workbook<RS>(
+    reader: RS,
+    args: &Option<ConversionOptions>,
+) -> Result<Sheets<RS>, MarkitdownError>
+where
+    RS: Read + Seek + Clone,
+{
+    let ext = args
+        .as_ref()
+        .and_then(|opts| opts.file_extension.as_deref());
+    let workbook = match ext {
+        Some(".xls") | Some(".xla") => open_workbook_from_rs::<Xls<_>, _>(reader.clone())
+            .ok()
+            .map(Sheets::Xls),
+        Some(".xlsx") | Some(".xlsm") | Some(".xlam") => {
+            open_workbook_from_rs::<Xlsx<_>, _>(reader.clone())
+                .ok()
+                .map(Sheets::Xlsx)
+        }
+        Some(".xlsb") => open_workbook_from_rs::<Xlsb<_>, _>(reader.clone())
+            .ok()
+            .map(Sheets::Xlsb),
+        Some(".ods") => open_workbook_from_rs::<Ods<_>, _>(reader.clone())
+            .ok()
+            .map(Sheets::Ods),
+        _ => None,
+    };
+
+    match workbook {
+        Some(workbook) => Ok(workbook),
+        None => open_workbook_auto_from_rs(reader)
+            .map_err(|e| MarkitdownError::ParseError(format!("Failed to open Excel file: {}", e))),
+    }
+}
+
+fn workbook_to_markdown(
+    bytes: &[u8],
+    args: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    let options = args.clone().unwrap_or_default();
+    let mut workbook = open_workbook(Cursor::new(bytes), args)?;
+    // Number formats and comments are only available for the OOXML package,
+    // the other readers fall back to what calamine exposes.
+    let mut package = match workbook {
+        Sheets::Xlsx(_) => XlsxPackage::open(bytes),
+        _ => None,
+    };
+
+    let sheet_names = workbook.sheet_names();
+    let tables = excel_tables(&mut workbook);
+    let selection = match &options.excel_range {
+        Some(spec) => Some(resolve_selection(spec, &workbook, &tables).ok_or_else(|| {
+            MarkitdownError::InvalidOption(format!("Unknown range or name: {}", spec))
+        })?),
+        None => None,
+    };
+
+    let mut markdown = String::new();
+
+    for sheet_name in &sheet_names {
+        if selection
+            .as_ref()
+            .is_some_and(|selection| &selection.sheet != sheet_name)
+        {
+            continue;
+        }
+        let Ok(range) = workbook.worksheet_range(sheet_name) else {
+            continue;
+        };
+
+        let formats = package
+            .as_mut()
+            .map(|package| package.cell_formats(sheet_name))
+            .unwrap_or_default();
+        let mut grid = SheetGrid::new(&range, &formats);
+
+        if options.excel_merged_cells == MergedCellMode::Repeat {
+            for region in merged_regions(&mut workbook, sheet_name) {
+                grid.repeat_merged(&region);
             }
+        }
 
-            markdown.push_str("|");
-            for cell in &rows[0] {
-                markdown.push_str(&format!(" {} |", cell));
+        if options.excel_formulas {
+            if let Ok(formulas) = workbook.worksheet_formula(sheet_name) {
+                grid.annotate_formulas(&formulas);
             }
-            markdown.push_str("\n|");
+        }
 
-            for _ in &rows[0] {
-                markdown.push_str(" --- |");
+        let comments = package
+            .as_mut()
+            .map(|package| package.comments(sheet_name))
+            .unwrap_or_default();
+        let mut footnotes = Vec::new();
+        for comment in comments {
+            // Footnote labels must be unique across the whole document.
+            let label = if sheet_names.len() > 1 {
+                format!(
+                    "{}-{}",
+                    sheet_name.replace(char::is_whitespace, "_"),
+                    comment.reference
+                )
+            } else {
+                comment.reference.clone()
+            };
+            if let Some(cell) = grid.cell_mut(comment.position) {
+                cell.push_str(&format!(" [^{}]", label));
+                footnotes.push(comment.footnote(&label));
             }
-            markdown.push_str("\n");
+        }
+
+        markdown.push_str(&format!("## {}\n\n", sheet_name));
 
-            for row in rows.iter().skip(1) {
-                markdown.push_str("|");
-                for cell in row {
-                    markdown.push_str(&format!(" {} |", cell));
+        let mut blocks = Vec::new();
+        if let Some(selection) = &selection {
+            blocks.push(Block {
+                start: selection.area.start,
+                heading: selection.name.clone(),
+                rows: grid
+                    .clamp(&selection.area)
+                    .map(|area| grid.area(&area))
+                    .unwrap_or_default(),
+            });
+        } else {
+            for table in tables.iter().filter(|table| &table.sheet == sheet_name) {
+                let mut rows = vec![table.columns.clone()];
+                rows.extend(grid.area(&table.data));
+                let header = Dimensions::new(
+                    (table.data.start.0.saturating_sub(1), table.data.start.1),
+                    (table.data.start.0.saturating_sub(1), table.data.end.1),
+                );
+                if grid.area(&header).first() == Some(&table.columns) {
+                    grid.clear(&header);
                 }
-                markdown.push_str("\n");
+                grid.clear(&table.data);
+                blocks.push(Block {
+                    start: header.start,
+                    heading: Some(table.name.clone()),
+                    rows,
+                });
             }
+            for region in grid.regions() {
+                blocks.push(Block {
+                    start: region.start,
+                    heading: None,
+                    rows: grid.area(&region),
+                });
+            }
+        }
+        // Tables and free data regions come out in reading order.
+        blocks.sort_by_key(|block| block.start);
+
+        for block in blocks {
+            let table = rows_to_markdown(&trim_empty(block.rows));
+            if table.is_empty() {
+                continue;
+            }
+            if let Some(heading) = block.heading {
+                markdown.push_str(&format!("### {}\n\n", heading));
+            }
+            markdown.push_str(&table);
+            markdown.push('\n');
+        }
+
+        for footnote in footnotes {
+            markdown.push_str(&footnote);
+            markdown.push('\n');
         }
+    }
 
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content: markdown,
+    Ok(DocumentConverterResult {
+        title: None,
+        text_content: markdown.trim_end().to_string(),
+        ..Default::default()
+    })
+}
+
+/// A table rendered from a sheet, positioned by its top-left cell.
+struct Block {
+    start: (u32, u32),
+    heading: Option<String>,
+    rows: Vec<Vec<String>>,
+}
+
+/// A defined Excel Table (`xl/tables/*.xml`) located by its data rows.
+struct ExcelTable {
+    name: String,
+    sheet: String,
+    columns: Vec<String>,
+    data: Dimensions,
+}
+
+fn excel_tables<RS>(workbook: &mut Sheets<RS>) -> Vec<ExcelTable>
+where
+    RS: Read + Seek,
+{
+    let Sheets::Xlsx(xlsx) = workbook else {
+        return Vec::new();
+    };
+    if xlsx.load_tables().is_err() {
+        return Vec::new();
+    }
+    let names: Vec<String> = xlsx.table_names().into_iter().cloned().collect();
+    names
+        .into_iter()
+        .filter_map(|name| {
+            let table = xlsx.table_by_name(&name).ok()?;
+            let data = Dimensions::new(table.data().start()?, table.data().end()?);
+            Some(ExcelTable {
+                name,
+                sheet: table.sheet_name().to_string(),
+                columns: table.columns().to_vec(),
+                data,
+            })
         })
+        .collect()
+}
+
+/// The part of a workbook requested through `ConversionOptions::excel_range`.
+struct Selection {
+    sheet: String,
+    area: Dimensions,
+    name: Option<String>,
+}
+
+/// Resolves an Excel Table name, a defined name or an `A1:F200` reference
+/// (optionally prefixed with `Sheet!`) against the workbook.
+fn resolve_selection<RS>(
+    spec: &str,
+    workbook: &Sheets<RS>,
+    tables: &[ExcelTable],
+) -> Option<Selection>
+where
+    RS: Read + Seek,
+{
+    if let Some(table) = tables
+        .iter()
+        .find(|table| table.name.eq_ignore_ascii_case(spec))
+    {
+        return Some(Selection {
+            sheet: table.sheet.clone(),
+            area: Dimensions::new(
+                (table.data.start.0.saturating_sub(1), table.data.start.1),
+                table.data.end,
+            ),
+            name: Some(table.name.clone()),
+        });
     }
 
-    fn convert_bytes(
-        &self,
-        bytes: &[u8],
-        args: Option<ConversionOptions>,
-    ) -> Result<DocumentConverterResult, MarkitdownError> {
-        if let Some(opts) = &args {
-            if let Some(ext) = &opts.file_extension {
-                if ext != ".xlsx" && ext != ".xls" {
-                    return Err(MarkitdownError::InvalidFile(
-                        format!("Expected .xlsx or .xls file, got {}", ext)
-                    ));
+    let (reference, name) = match workbook
+        .defined_names()
+        .iter()
+        .find(|(name, _)| name.eq_ignore_ascii_case(spec))
+    {
+        Some((name, formula)) => (
+            formula.trim_start_matches('=').to_string(),
+            Some(name.clone()),
+        ),
+        None => (spec.to_string(), None),
+    };
+
+    let (sheet, area) = match reference.rsplit_once('!') {
+        Some((sheet, area)) => (
+            sheet.trim_matches('\'').replace("''", "'"),
+            area.to_string(),
+        ),
+        None => (workbook.sheet_names().first()?.clone(), reference),
+    };
+    let (start, end) = area.split_once(':').unwrap_or((&area, &area));
+    Some(Selection {
+        sheet,
+        area: Dimensions::new(parse_cell_ref(start)?, parse_cell_ref(end)?),
+        name,
+    })
+}
+
+fn merged_regions<RS>(workbook: &mut Sheets<RS>, sheet_name: &str) -> Vec<Dimensions>
+where
+    RS: Read + Seek,
+{
+    match workbook {
+        Sheets::Xlsx(xlsx) => xlsx
+            .worksheet_merge_cells(sheet_name)
+            .and_then(Result::ok)
+            .unwrap_or_default(),
+        Sheets::Xls(xls) => xls.worksheet_merge_cells(sheet_name).unwrap_or_default(),
+        _ => Vec::new(),
+    }
+}
+
+/// Rendered cells of a sheet together with the absolute position of the
+/// top-left cell, so merges, formulas and comments can be located.
+struct SheetGrid {
+    origin: (u32, u32),
+    cells: Vec<Vec<String>>,
+}
+
+impl SheetGrid {
+    /// Renders every cell of `range` using the number format of its position.
+    fn new(range: &Range<Data>, formats: &HashMap<(u32, u32), String>) -> Self {
+        let origin = range.start().unwrap_or((0, 0));
+        let cells = range
+            .rows()
+            .enumerate()
+            .map(|(i, row)| {
+                row.iter()
+                    .enumerate()
+                    .map(|(j, cell)| {
+                        let position = (origin.0 + i as u32, origin.1 + j as u32);
+                        format_cell(cell, formats.get(&position).map(String::as_str))
+                    })
+                    .collect()
+            })
+            .collect();
+        SheetGrid { origin, cells }
+    }
+
+    fn cell(&self, (row, col): (u32, u32)) -> Option<&str> {
+        let row = row.checked_sub(self.origin.0)? as usize;
+        let col = col.checked_sub(self.origin.1)? as usize;
+        self.cells.get(row)?.get(col).map(String::as_str)
+    }
+
+    fn cell_mut(&mut self, (row, col): (u32, u32)) -> Option<&mut String> {
+        let row = row.checked_sub(self.origin.0)? as usize;
+        let col = col.checked_sub(self.origin.1)? as usize;
+        self.cells.get_mut(row)?.get_mut(col)
+    }
+
+    /// The part of an absolute area inside the grid, `None` when they don't
+    /// overlap.
+    fn clamp(&self, area: &Dimensions) -> Option<Dimensions> {
+        let height = self.cells.len() as u32;
+        let width = self.cells.iter().map(Vec::len).max().unwrap_or(0) as u32;
+        if height == 0 || width == 0 {
+            return None;
+        }
+        let start = (
+            area.start.0.max(self.origin.0),
+            area.start.1.max(self.origin.1),
+        );
+        let end = (
+            area.end.0.min(self.origin.0 + height - 1),
+            area.end.1.min(self.origin.1 + width - 1),
+        );
+        (start.0 <= end.0 && start.1 <= end.1).then(|| Dimensions::new(start, end))
+    }
+
+    fn repeat_merged(&mut self, region: &Dimensions) {
+        let Some(value) = self.cell_mut(region.start).map(|cell| cell.clone()) else {
+            return;
+        };
+        let Some(region) = self.clamp(region) else {
+            return;
+        };
+        for row in region.start.0..=region.end.0 {
+            for col in region.start.1..=region.end.1 {
+                if let Some(cell) = self.cell_mut((row, col)) {
+                    cell.clone_from(&value);
                 }
             }
         }
-        let reader = Cursor::new(bytes);
-        let mut workbook: Xlsx<_> = Xlsx::new(reader)
-            .map_err(|e| MarkitdownError::ParseError(format!("Failed to open Excel file: {}", e)))?;
+    }
 
-        let mut markdown = String::new();
+    /// Copies the cells of an absolute area, padding positions before the
+    /// grid with empty strings.
+    fn area(&self, area: &Dimensions) -> Vec<Vec<String>> {
+        // Everything past the grid is blank, so there is no need to pad
+        // beyond its last row and column.
+        let Some(inside) = self.clamp(area) else {
+            return Vec::new();
+        };
+        (area.start.0..=inside.end.0)
+            .map(|row| {
+                (area.start.1..=inside.end.1)
+                    .map(|col| self.cell((row, col)).unwrap_or_default().to_string())
+                    .collect()
+            })
+            .collect()
+    }
+
+    fn clear(&mut self, area: &Dimensions) {
+        let Some(area) = self.clamp(area) else {
+            return;
+        };
+        for row in area.start.0..=area.end.0 {
+            for col in area.start.1..=area.end.1 {
+                if let Some(cell) = self.cell_mut((row, col)) {
+                    cell.clear();
+                }
+            }
+        }
+    }
 
-        if let Some(Ok(range)) = workbook.worksheet_range_at(0) {
-            let rows: Vec<Vec<String>> = range
-                .rows()
-                .map(|row| row.iter().map(|cell| cell.to_string()).collect())
-                .collect();
+    /// Splits the sheet into data regions separated by fully blank rows or
+    /// columns, returned as absolute areas.
+    fn regions(&self) -> Vec<Dimensions> {
+        let height = self.cells.len();
+        let width = self.cells.iter().map(Vec::len).max().unwrap_or(0);
+        let mut regions = Vec::new();
+        if height > 0 && width > 0 {
+            self.split_regions(0..height, 0..width, &mut regions);
+        }
+        regions
+    }
 
-            if rows.is_empty() {
-                return Ok(DocumentConverterResult {
-                    title: None,
-                    text_content: String::new(),
-                });
+    fn split_regions(
+        &self,
+        rows: std::ops::Range<usize>,
+        cols: std::ops::Range<usize>,
+        regions: &mut Vec<Dimensions>,
+    ) {
+        let filled = |row: usize, col: usize| {
+            self.cells
+                .get(row)
+                .and_then(|cells| cells.get(col))
+                .is_some_and(|cell| !cell.is_empty())
+        };
+        let row_runs = runs(rows.clone(), |row| cols.clone().any(|col| filled(row, col)));
+        if row_runs.len() > 1 || row_runs.first() != Some(&rows) {
+            for run in row_runs {
+                self.split_regions(run, cols.clone(), regions);
+            }
+            return;
+        }
+        let col_runs = runs(cols.clone(), |col| rows.clone().any(|row| filled(row, col)));
+        if col_runs.len() > 1 || col_runs.first() != Some(&cols) {
+            for run in col_runs {
+                self.split_regions(rows.clone(), run, regions);
             }
+            return;
+        }
+        regions.push(Dimensions::new(
+            (
+                self.origin.0 + rows.start as u32,
+                self.origin.1 + cols.start as u32,
+            ),
+            (
+                self.origin.0 + rows.end as u32 - 1,
+                self.origin.1 + cols.end as u32 - 1,
+            ),
+        ));
+    }
 
-            markdown.push_str("|");
-            for cell in &rows[0] {
-                markdown.push_str(&format!(" {} |", cell));
+    fn annotate_formulas(&mut self, formulas: &Range<String>) {
+        let origin = formulas.start().unwrap_or((0, 0));
+        for (row, col, formula) in formulas.used_cells() {
+            let position = (origin.0 + row as u32, origin.1 + col as u32);
+            if let Some(cell) = self.cell_mut(position) {
+                cell.push_str(&format!(" (`={}`)", formula));
             }
-            markdown.push_str("\n|");
+        }
+    }
+}
 
-            for _ in &rows[0] {
-                markdown.push_str(" --- |");
+/// Maximal runs of consecutive indices satisfying `keep`.
+fn runs(
+    indices: std::ops::Range<usize>,
+    keep: impl Fn(usize) -> bool,
+) -> Vec<std::ops::Range<usize>> {
+    let mut runs = Vec::new();
+    let mut start = None;
+    for index in indices.clone() {
+        match (keep(index), start) {
+            (true, None) => start = Some(index),
+            (false, Some(run_start)) => {
+                runs.push(run_start..index);
+                start = None;
             }
-            markdown.push_str("\n");
+            _ => {}
+        }
+    }
+    if let Some(run_start) = start {
+        runs.push(run_start..indices.end);
+    }
+    runs
+}
+
+/// Drops empty rows and columns around the data so stray formatted cells do
+/// not widen the table.
+fn trim_empty(mut rows: Vec<Vec<String>>) -> Vec<Vec<String>> {
+    let is_empty_row = |row: &Vec<String>| row.iter().all(|cell| cell.is_empty());
+    while rows.last().is_some_and(is_empty_row) {
+        rows.pop();
+    }
+    let leading = rows.iter().take_while(|row| is_empty_row(row)).count();
+    rows.drain(..leading);
+
+    let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+    let used = |col: usize| {
+        rows.iter()
+            .any(|row| row.get(col).is_some_and(|c| !c.is_empty()))
+    };
+    let first = (0..width).find(|&col| used(col)).unwrap_or(0);
+    let last = (0..width)
+        .rev()
+        .find(|&col| used(col))
+        .map_or(0, |col| col + 1);
+
+    rows.into_iter()
+        .map(|mut row| {
+            row.resize(width, String::new());
+            row[first..last.max(first)].to_vec()
+        })
+        .collect()
+}
+
+fn format_cell(cell: &Data, format: Option<&str>) -> String {
+    let format = format.filter(|code| !code.eq_ignore_ascii_case("general"));
+    let formatted = match cell {
+        Data::Empty => String::new(),
+        Data::String(s) => s.clone(),
+        Data::Bool(b) => if *b { "TRUE" } else { "FALSE" }.to_string(),
+        Data::Error(e) => e.to_string(),
+        Data::DateTimeIso(s) | Data::DurationIso(s) => s.clone(),
+        Data::Int(i) => format
+            .and_then(|code| apply_number_format(*i as f64, None, code))
+            .unwrap_or_else(|| i.to_string()),
+        Data::Float(f) => format
+            .and_then(|code| apply_number_format(*f, None, code))
+            .unwrap_or_else(|| format_general(*f)),
+        Data::DateTime(dt) => {
+            let datetime = if dt.is_datetime() {
+                dt.as_datetime()
+            } else {
+                None
+            };
+            format
+                .and_then(|code| apply_number_format(dt.as_f64(), datetime, code))
+                .or_else(|| datetime.map(format_iso_datetime))
+                .or_else(|| apply_number_format(dt.as_f64(), None, "[h]:mm:ss"))
+                .unwrap_or_else(|| format_general(dt.as_f64()))
+        }
+    };
+    formatted.trim().to_string()
+}
+
+/// Mirrors Excel's "General" format: integral values without a fraction and
+/// at most ten significant digits otherwise.
+fn format_general(value: f64) -> String {
+    if value.fract() == 0.0 && value.abs() < 1e15 {
+        return format!("{}", value as i64);
+    }
+    let magnitude = value.abs().log10().floor() as i32;
+    let decimals = (9 - magnitude).clamp(0, 15) as usize;
+    trim_fraction(&format!("{:.*}", decimals, value), 0)
+}
+
+fn format_iso_datetime(datetime: NaiveDateTime) -> String {
+    if datetime.time() == chrono::NaiveTime::MIN {
+        datetime.format("%Y-%m-%d").to_string()
+    } else {
+        datetime.format("%Y-%m-%d %H:%M:%S").to_string()
+    }
+}
+
+/// Removes trailing fraction zeros while keeping at least `min_decimals`.
+fn trim_fraction(number: &str, min_decimals: usize) -> String {
+    let Some(dot) = number.find('.') else {
+        return number.to_string();
+    };
+    let mut end = number.len();
+    while end > dot + 1 + min_decimals && number.as_bytes()[end - 1] == b'0' {
+        end -= 1;
+    }
+    if end == dot + 1 {
+        end = dot;
+    }
+    number[..end].to_string()
+}
+
+/// Built-in number formats that workbooks reference by id only. Locale
+/// dependent date formats are rendered as ISO dates.
+fn builtin_format(id: u32) -> Option<&'static str> {
+    Some(match id {
+        0 => "General",
+        1 => "0",
+        2 => "0.00",
+        3 => "#,##0",
+        4 => "#,##0.00",
+        5 => "$#,##0_);($#,##0)",
+        6 => "$#,##0_);[Red]($#,##0)",
+        7 => "$#,##0.00_);($#,##0.00)",
+        8 => "$#,##0.00_);[Red]($#,##0.00)",
+        9 => "0%",
+        10 => "0.00%",
+        11 => "0.00E+00",
+        14 => "yyyy-mm-dd",
+        15 => "d-mmm-yy",
+        16 => "d-mmm",
+        17 => "mmm-yy",
+        18 => "h:mm AM/PM",
+        19 => "h:mm:ss AM/PM",
+        20 => "h:mm",
+        21 => "h:mm:ss",
+        22 => "yyyy-mm-dd h:mm",
+        37 => "#,##0_);(#,##0)",
+        38 => "#,##0_);[Red](#,##0)",
+        39 => "#,##0.00_);(#,##0.00)",
+        40 => "#,##0.00_);[Red](#,##0.00)",
+        45 => "mm:ss",
+        46 => "[h]:mm:ss",
+        47 => "mm:ss.0",
+        48 => "##0.0E+0",
+        49 => "@",
+        _ => return None,
+    })
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum FormatToken {
+    Literal(String),
+    Char(char),
+    ElapsedHours,
+}
+
+/// Applies an Excel number format code to a value. Returns `None` for codes
+/// this renderer does not understand (fractions, conditions) so the caller
+/// can fall back to the general representation.
+fn apply_number_format(value: f64, datetime: Option<NaiveDateTime>, code: &str) -> Option<String> {
+    // Sections are `positive;negative;zero;text`, negative values in their
+    // own section are rendered without the minus sign.
+    let sections = split_sections(code);
+    let (section, value, sign) = if value < 0.0 && sections.len() >= 2 {
+        (sections[1].as_str(), -value, "")
+    } else if value == 0.0 && sections.len() >= 3 {
+        (sections[2].as_str(), value, "")
+    } else if value < 0.0 {
+        (sections[0].as_str(), -value, "-")
+    } else {
+        (sections[0].as_str(), value, "")
+    };
+
+    let tokens = tokenize_format(section);
+    let chars = || {
+        tokens.iter().filter_map(|token| match token {
+            FormatToken::Char(c) => Some(c.to_ascii_lowercase()),
+            _ => None,
+        })
+    };
+
+    if chars().any(|c| c == '@') || section.eq_ignore_ascii_case("general") {
+        return Some(format!("{}{}", sign, format_general(value)));
+    }
+    if tokens.contains(&FormatToken::ElapsedHours)
+        || chars().any(|c| matches!(c, 'y' | 'd' | 'h' | 's' | 'm'))
+    {
+        let datetime = datetime.or_else(|| serial_to_datetime(value))?;
+        return Some(format_datetime(&tokens, datetime, value));
+    }
+    if chars().any(|c| c == '/') {
+        return None;
+    }
+    format_number(&tokens, value).map(|number| format!("{}{}", sign, number))
+}
 
-            for row in rows.iter().skip(1) {
-                markdown.push_str("|");
-                for cell in row {
-                    markdown.push_str(&format!(" {} |", cell));
+fn split_sections(code: &str) -> Vec<String> {
+    let mut sections = vec![String::new()];
+    let mut quoted = false;
+    let mut escaped = false;
+    for c in code.chars() {
+        if escaped {
+            escaped = false;
+        } else if c == '\\' {
+            escaped = true;
+        } else if c == '"' {
+            quoted = !quoted;
+        } else if c == ';' && !quoted {
+            sections.push(String::new());
+            continue;
+        }
+        if let Some(section) = sections.last_mut() {
+            section.push(c);
+        }
+    }
+    sections
+}
+
+fn tokenize_format(section: &str) -> Vec<FormatToken> {
+    let mut tokens = Vec::new();
+    let mut chars = section.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '"' => {
+                let literal: String = chars.by_ref().take_while(|&c| c != '"').collect();
+                tokens.push(FormatToken::Literal(literal));
+            }
+            '\\' => {
+                if let Some(next) = chars.next() {
+                    tokens.push(FormatToken::Literal(next.to_string()));
+                }
+            }
+            '_' => {
+                chars.next();
+                tokens.push(FormatToken::Literal(" ".to_string()));
+            }
+            '*' => {
+                chars.next();
+            }
+            '[' => {
+                let inner: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                if let Some(currency) = inner.strip_prefix('$') {
+                    let symbol = currency.split('-').next().unwrap_or_default();
+                    tokens.push(FormatToken::Literal(symbol.to_string()));
+                } else if inner.eq_ignore_ascii_case("h") || inner.eq_ignore_ascii_case("hh") {
+                    tokens.push(FormatToken::ElapsedHours);
+                }
+                // Colors and conditions do not affect the rendered text.
+            }
+            'A' | 'a' => {
+                let rest: String = chars.clone().take(4).collect();
+                if rest.eq_ignore_ascii_case("m/pm") {
+                    chars.nth(3);
+                    tokens.push(FormatToken::Literal("\u{0}AMPM".to_string()));
+                } else if rest.to_ascii_lowercase().starts_with("/p") {
+                    chars.nth(1);
+                    tokens.push(FormatToken::Literal("\u{0}AP".to_string()));
+                } else {
+                    tokens.push(FormatToken::Char(c));
+                }
+            }
+            _ => tokens.push(FormatToken::Char(c)),
+        }
+    }
+    tokens
+}
+
+fn serial_to_datetime(serial: f64) -> Option<NaiveDateTime> {
+    // Serials below 60 predate Excel's phantom 1900-02-29.
+    let serial = if serial < 60.0 { serial + 1.0 } else { serial };
+    let epoch = NaiveDate::from_ymd_opt(1899, 12, 30)?.and_hms_opt(0, 0, 0)?;
+    let ms = (serial * 86_400_000.0).round() as i64;
+    epoch.checked_add_signed(chrono::Duration::milliseconds(ms))
+}
+
+fn format_datetime(tokens: &[FormatToken], datetime: NaiveDateTime, serial: f64) -> String {
+    const MONTHS: [&str; 12] = [
+        "January",
+        "February",
+        "March",
+        "April",
+        "May",
+        "June",
+        "July",
+        "August",
+        "September",
+        "October",
+        "November",
+        "December",
+    ];
+    const DAYS: [&str; 7] = [
+        "Monday",
+        "Tuesday",
+        "Wednesday",
+        "Thursday",
+        "Friday",
+        "Saturday",
+        "Sunday",
+    ];
+
+    // Group consecutive identical letters into runs such as `yyyy` or `mm`.
+    let mut runs: Vec<(char, usize)> = Vec::new();
+    let mut literals: Vec<Option<String>> = Vec::new();
+    for token in tokens {
+        match token {
+            FormatToken::Char(c) if "ymdhs0".contains(c.to_ascii_lowercase()) => {
+                let c = c.to_ascii_lowercase();
+                match runs.last_mut() {
+                    Some((last, count)) if *last == c && literals.last() == Some(&None) => {
+                        *count += 1
+                    }
+                    _ => {
+                        runs.push((c, 1));
+                        literals.push(None);
+                    }
+                }
+            }
+            FormatToken::ElapsedHours => {
+                runs.push(('H', 1));
+                literals.push(None);
+            }
+            FormatToken::Char(c) => {
+                runs.push((' ', 0));
+                literals.push(Some(c.to_string()));
+            }
+            FormatToken::Literal(s) => {
+                runs.push((' ', 0));
+                literals.push(Some(s.clone()));
+            }
+        }
+    }
+
+    let twelve_hour = literals
+        .iter()
+        .flatten()
+        .any(|literal| literal.starts_with('\u{0}'));
+    let kinds: Vec<char> = runs.iter().map(|(c, _)| *c).collect();
+    let is_minute = |index: usize| {
+        let previous = kinds[..index]
+            .iter()
+            .rev()
+            .find(|c| matches!(c, 'y' | 'm' | 'd' | 'h' | 'H' | 's'));
+        let next = kinds[index + 1..]
+            .iter()
+            .find(|c| matches!(c, 'y' | 'm' | 'd' | 'h' | 'H' | 's'));
+        matches!(previous, Some('h') | Some('H')) || next == Some(&'s')
+    };
+
+    let mut output = String::new();
+    for (index, ((kind, count), literal)) in runs.iter().zip(&literals).enumerate() {
+        if let Some(literal) = literal {
+            match literal.as_str() {
+                "\u{0}AMPM" => output.push_str(if datetime.hour() < 12 { "AM" } else { "PM" }),
+                "\u{0}AP" => output.push(if datetime.hour() < 12 { 'A' } else { 'P' }),
+                _ => output.push_str(literal),
+            }
+            continue;
+        }
+        let count = *count;
+        match kind {
+            'y' if count <= 2 => output.push_str(&format!("{:02}", datetime.year() % 100)),
+            'y' => output.push_str(&format!("{:04}", datetime.year())),
+            'm' if is_minute(index) => output.push_str(&pad(datetime.minute(), count)),
+            'm' => match count {
+                1 | 2 => output.push_str(&pad(datetime.month(), count)),
+                3 => output.push_str(&MONTHS[datetime.month0() as usize][..3]),
+                4 => output.push_str(MONTHS[datetime.month0() as usize]),
+                _ => output.push_str(&MONTHS[datetime.month0() as usize][..1]),
+            },
+            'd' => match count {
+                1 | 2 => output.push_str(&pad(datetime.day(), count)),
+                3 => {
+                    output.push_str(&DAYS[datetime.weekday().num_days_from_monday() as usize][..3])
                 }
-                markdown.push_str("\n");
+                _ => output.push_str(DAYS[datetime.weekday().num_days_from_monday() as usize]),
+            },
+            'h' => {
+                let hour = if twelve_hour {
+                    (datetime.hour() + 11) % 12 + 1
+                } else {
+                    datetime.hour()
+                };
+                output.push_str(&pad(hour, count));
             }
+            'H' => output.push_str(&((serial * 24.0).floor() as i64).to_string()),
+            's' => output.push_str(&pad(datetime.second(), count)),
+            '0' => {
+                let fraction = datetime.nanosecond() as f64 / 1e9;
+                let digits = format!("{:.*}", count, fraction);
+                output.push_str(digits.trim_start_matches('0').trim_start_matches('.'));
+            }
+            _ => {}
         }
+    }
+    output
+}
+
+fn pad(value: u32, width: usize) -> String {
+    if width >= 2 {
+        format!("{:02}", value)
+    } else {
+        value.to_string()
+    }
+}
+
+fn format_number(tokens: &[FormatToken], value: f64) -> Option<String> {
+    let is_digit = |token: &FormatToken| matches!(token, FormatToken::Char('0' | '#' | '?'));
+    let first = tokens.iter().position(is_digit)?;
+    let last = tokens.iter().rposition(is_digit)?;
+
+    let percent = tokens
+        .iter()
+        .filter(|token| **token == FormatToken::Char('%'))
+        .count();
+    let mut value = value * 100f64.powi(percent as i32);
+
+    let pattern: String = tokens[first..=last]
+        .iter()
+        .filter_map(|token| match token {
+            FormatToken::Char(c) => Some(*c),
+            _ => None,
+        })
+        .collect();
+
+    // Commas directly after the last digit placeholder scale by thousands.
+    let mut suffix_start = last + 1;
+    while tokens.get(suffix_start) == Some(&FormatToken::Char(',')) {
+        value /= 1000.0;
+        suffix_start += 1;
+    }
+    // A decimal point right after the digits belongs to the number.
+    let trailing_dot = tokens.get(suffix_start) == Some(&FormatToken::Char('.'));
+    if trailing_dot {
+        suffix_start += 1;
+    }
+
+    let number = if let Some(exponent) = pattern.to_ascii_uppercase().find('E') {
+        format_scientific(value, &pattern[..exponent], &pattern[exponent..])
+    } else {
+        format_fixed(value, &pattern)
+    };
+
+    let mut output = render_literals(&tokens[..first]);
+    output.push_str(&number);
+    if trailing_dot {
+        output.push('.');
+    }
+    output.push_str(&render_literals(&tokens[suffix_start..]));
+    Some(output)
+}
 
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content: markdown,
+fn render_literals(tokens: &[FormatToken]) -> String {
+    tokens
+        .iter()
+        .map(|token| match token {
+            FormatToken::Literal(s) => s.clone(),
+            FormatToken::Char(c) => c.to_string(),
+            FormatToken::ElapsedHours => String::new(),
         })
+        .collect()
+}
+
+fn format_fixed(value: f64, pattern: &str) -> String {
+    let (integer, fraction) = pattern.split_once('.').unwrap_or((pattern, ""));
+    let min_decimals = fraction.chars().filter(|&c| c == '0').count();
+    let max_decimals = fraction.chars().filter(|c| "0#?".contains(*c)).count();
+    let min_integers = integer.chars().filter(|&c| c == '0').count();
+    let grouping = integer.contains(',');
+
+    // Excel rounds halves away from zero, `format!` rounds them to even.
+    let scale = 10f64.powi(max_decimals as i32);
+    let rounded = format!("{:.*}", max_decimals, (value * scale).round() / scale);
+    let rounded = trim_fraction(&rounded, min_decimals);
+    let (int_part, frac_part) = match rounded.split_once('.') {
+        Some((int_part, frac_part)) => (int_part.to_string(), Some(frac_part.to_string())),
+        None => (rounded, None),
+    };
+
+    let mut int_part = int_part.trim_start_matches('0').to_string();
+    while int_part.len() < min_integers {
+        int_part.insert(0, '0');
+    }
+    if grouping {
+        int_part = group_thousands(&int_part);
+    }
+
+    match frac_part {
+        Some(frac_part) => format!("{}.{}", int_part, frac_part),
+        None => int_part,
+    }
+}
+
+fn group_thousands(digits: &str) -> String {
+    let mut grouped = String::new();
+    for (i, c) in digits.chars().enumerate() {
+        if i > 0 && (digits.len() - i) % 3 == 0 {
+            grouped.push(',');
+        }
+        grouped.push(c);
+    }
+    grouped
+}
+
+fn format_scientific(value: f64, mantissa: &str, exponent: &str) -> String {
+    let decimals = mantissa
+        .split_once('.')
+        .map_or(0, |(_, fraction)| fraction.len());
+    let exponent_digits = exponent.chars().filter(|c| "0#?".contains(*c)).count();
+    let explicit_plus = exponent.contains('+');
+
+    let formatted = format!("{:.*e}", decimals, value);
+    let (mantissa, power) = formatted.split_once('e').unwrap_or((&formatted, "0"));
+    let power: i32 = power.parse().unwrap_or(0);
+    let sign = if power < 0 {
+        "-"
+    } else if explicit_plus {
+        "+"
+    } else {
+        ""
+    };
+    format!(
+        "{}E{}{:0width$}",
+        mantissa,
+        sign,
+        power.abs(),
+        width = exponent_digits
+    )
+}
+
+struct Relationship {
+    id: String,
+    kind: String,
+    target: String,
+}
+
+/// A cell note, keyed by its `A1` reference.
+struct CellComment {
+    reference: String,
+    position: (u32, u32),
+    author: Option<String>,
+    text: String,
+}
+
+impl CellComment {
+    /// Renders the comment as a markdown footnote definition.
+    fn footnote(&self, label: &str) -> String {
+        let text = self.text.split_whitespace().collect::<Vec<_>>().join(" ");
+        match &self.author {
+            // Excel prefixes notes with "Author:" itself.
+            Some(author) if !text.starts_with(&format!("{}:", author)) => {
+                format!("[^{}]: {}: {}", label, author, text)
+            }
+            _ => format!("[^{}]: {}", label, text),
+        }
+    }
+}
+
+/// Raw parts of an `.xlsx` package that calamine does not expose.
+struct XlsxPackage<'a> {
+    archive: ZipArchive<Cursor<&'a [u8]>>,
+}
+
+impl<'a> XlsxPackage<'a> {
+    fn open(bytes: &'a [u8]) -> Option<Self> {
+        let archive = ZipArchive::new(Cursor::new(bytes)).ok()?;
+        Some(XlsxPackage { archive })
+    }
+
+    fn read(&mut self, name: &str) -> Option<String> {
+        let mut file = self.archive.by_name(name).ok()?;
+        let mut content = String::new();
+        file.read_to_string(&mut content).ok()?;
+        Some(content)
+    }
+
+    /// Resolves the worksheet part of `sheet_name` through the workbook
+    /// relationships.
+    fn sheet_path(&mut self, sheet_name: &str) -> Option<String> {
+        let workbook = self.read("xl/workbook.xml")?;
+        let mut relationship = None;
+        for_each_element(&workbook, |name, attrs| {
+            if name == "sheet" && attrs.get("name").map(String::as_str) == Some(sheet_name) {
+                relationship = attrs.get("r:id").cloned();
+            }
+        });
+        let relationship = relationship?;
+        self.relationships("xl/workbook.xml")
+            .into_iter()
+            .find(|rel| rel.id == relationship)
+            .map(|rel| rel.target)
+    }
+
+    /// Relationships of `part` with targets resolved to package paths.
+    fn relationships(&mut self, part: &str) -> Vec<Relationship> {
+        let (dir, file) = part.rsplit_once('/').unwrap_or(("", part));
+        let rels_path = format!("{}/_rels/{}.rels", dir, file);
+        let mut relationships = Vec::new();
+        if let Some(rels) = self.read(&rels_path) {
+            for_each_element(&rels, |name, attrs| {
+                if let ("Relationship", Some(id), Some(kind), Some(target)) = (
+                    name,
+                    attrs.get("Id"),
+                    attrs.get("Type"),
+                    attrs.get("Target"),
+                ) {
+                    relationships.push(Relationship {
+                        id: id.clone(),
+                        kind: kind.clone(),
+                        target: resolve_part(dir, target),
+                    });
+                }
+            });
+        }
+        relationships
+    }
+
+    /// Legacy cell comments (notes) of a sheet in document order.
+    fn comments(&mut self, sheet_name: &str) -> Vec<CellComment> {
+        let Some(sheet_path) = self.sheet_path(sheet_name) else {
+            return Vec::new();
+        };
+        let Some(xml) = self
+            .relationships(&sheet_path)
+            .into_iter()
+            .find(|rel| rel.kind.ends_with("/comments"))
+            .and_then(|rel| self.read(&rel.target))
+        else {
+            return Vec::new();
+        };
+
+        let mut authors = Vec::new();
+        let mut comments = Vec::new();
+        let mut current: Option<CellComment> = None;
+        let mut text = String::new();
+        let mut in_text = false;
+        let mut reader = quick_xml::Reader::from_str(&xml);
+        loop {
+            match reader.read_event() {
+                Ok(Event::Start(element)) => match element.local_name().as_ref() {
+                    b"author" | b"t" => {
+                        in_text = true;
+                        text.clear();
+                    }
+                    b"comment" => {
+                        let (_, attrs) = element_parts(&element, reader.decoder());
+                        let reference = attrs.get("ref").cloned().unwrap_or_default();
+                        current = parse_cell_ref(&reference).map(|position| CellComment {
+                            reference,
+                            position,
+                            author: attrs
+                                .get("authorId")
+                                .and_then(|id| id.parse::<usize>().ok())
+                                .and_then(|id| authors.get(id).cloned()),
+                            text: String::new(),
+                        });
+                    }
+                    _ => {}
+                },
+                Ok(Event::Text(t)) if in_text => {
+                    if let Ok(t) = t.unescape() {
+                        text.push_str(&t);
+                    }
+                }
+                Ok(Event::End(element)) => match element.local_name().as_ref() {
+                    b"author" => {
+                        in_text = false;
+                        authors.push(text.clone());
+                    }
+                    b"t" => {
+                        in_text = false;
+                        if let Some(comment) = current.as_mut() {
+                            comment.text.push_str(&text);
+                        }
+                    }
+                    b"comment" => comments.extend(current.take()),
+                    _ => {}
+                },
+                Ok(Event::Eof) | Err(_) => break,
+                _ => {}
+            }
+        }
+        comments
+    }
+
+    /// Number format code of each styled cell of a sheet, keyed by zero-based
+    /// `(row, column)`.
+    fn cell_formats(&mut self, sheet_name: &str) -> HashMap<(u32, u32), String> {
+        let mut formats = HashMap::new();
+        let (Some(styles), Some(sheet)) = (
+            self.read("xl/styles.xml"),
+            self.sheet_path(sheet_name)
+                .and_then(|path| self.read(&path)),
+        ) else {
+            return formats;
+        };
+
+        let mut custom = HashMap::new();
+        let mut xf_formats = Vec::new();
+        let mut in_cell_xfs = false;
+        for_each_event(&styles, |event, name, attrs| match (event, name) {
+            (XmlEvent::Start, "numFmt") => {
+                if let (Some(id), Some(code)) = (attrs.get("numFmtId"), attrs.get("formatCode")) {
+                    if let Ok(id) = id.parse::<u32>() {
+                        custom.insert(id, code.clone());
+                    }
+                }
+            }
+            (XmlEvent::Start, "cellXfs") => in_cell_xfs = true,
+            (XmlEvent::End, "cellXfs") => in_cell_xfs = false,
+            (XmlEvent::Start, "xf") if in_cell_xfs => {
+                let id = attrs
+                    .get("numFmtId")
+                    .and_then(|id| id.parse::<u32>().ok())
+                    .unwrap_or(0);
+                xf_formats.push(id);
+            }
+            _ => {}
+        });
+
+        for_each_element(&sheet, |name, attrs| {
+            if name != "c" {
+                return;
+            }
+            let (Some(reference), Some(style)) = (attrs.get("r"), attrs.get("s")) else {
+                return;
+            };
+            let Some(id) = style
+                .parse::<usize>()
+                .ok()
+                .and_then(|style| xf_formats.get(style))
+            else {
+                return;
+            };
+            let code = custom
+                .get(id)
+                .cloned()
+                .or_else(|| builtin_format(*id).map(str::to_string));
+            if let (Some(position), Some(code)) = (parse_cell_ref(reference), code) {
+                formats.insert(position, code);
+            }
+        });
+        formats
+    }
+}
+
+fn resolve_part(dir: &str, target: &str) -> String {
+    if let Some(absolute) = target.strip_prefix('/') {
+        return absolute.to_string();
+    }
+    let mut parts: Vec<&str> = dir.split('/').filter(|part| !part.is_empty()).collect();
+    for segment in target.split('/') {
+        match segment {
+            ".." => {
+                parts.pop();
+            }
+            "." | "" => {}
+            _ => parts.push(segment),
+        }
+    }
+    parts.join("/")
+}
+
+/// Last zero-based row and column of a worksheet, cell `XFD1048576`.
+const MAX_ROW: u32 = 1_048_575;
+const MAX_COL: u32 = 16_383;
+
+/// Parses an `A1` style reference into a zero-based `(row, column)`,
+/// rejecting positions past the end of a worksheet.
+fn parse_cell_ref(reference: &str) -> Option<(u32, u32)> {
+    let reference = reference.replace('$', "");
+    let split = reference.find(|c: char| c.is_ascii_digit())?;
+    let (letters, digits) = reference.split_at(split);
+    if letters.is_empty() {
+        return None;
+    }
+    let mut col = 0u32;
+    for c in letters.chars() {
+        if !c.is_ascii_alphabetic() {
+            return None;
+        }
+        col = col
+            .checked_mul(26)?
+            .checked_add(c.to_ascii_uppercase() as u32 - 'A' as u32 + 1)?;
+    }
+    let row: u32 = digits.parse().ok()?;
+    let (row, col) = (row.checked_sub(1)?, col - 1);
+    (row <= MAX_ROW && col <= MAX_COL).then_some((row, col))
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum XmlEvent {
+    Start,
+    End,
+}
+
+/// Walks every element of an XML document, passing its local name and
+/// attributes (keyed by their qualified name) to `f`.
+fn for_each_event<F>(xml: &str, mut f: F)
+where
+    F: FnMut(XmlEvent, &str, &HashMap<String, String>),
+{
+    let mut reader = quick_xml::Reader::from_str(xml);
+    let no_attrs = HashMap::new();
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let (name, attrs) = element_parts(&element, reader.decoder());
+                f(XmlEvent::Start, &name, &attrs);
+            }
+            Ok(Event::Empty(element)) => {
+                let (name, attrs) = element_parts(&element, reader.decoder());
+                f(XmlEvent::Start, &name, &attrs);
+                f(XmlEvent::End, &name, &no_attrs);
+            }
+            Ok(Event::End(element)) => {
+                let name = String::from_utf8_lossy(element.local_name().as_ref()).to_string();
+                f(XmlEvent::End, &name, &no_attrs);
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
     }
 }
+
+fn element_parts(element: &BytesStart, decoder: Decoder) -> (String, HashMap<String, String>) {
+    let name = String::from_utf8_lossy(element.local_name().as_ref()).to_string();
+    let attrs = element
+        .attributes()
+        .flatten()
+        .map(|attr| {
+            let key = String::from_utf8_lossy(attr.key.as_ref()).to_string();
+            let value = attr
+                .decode_and_unescape_value(decoder)
+                .map(|value| value.to_string())
+                .unwrap_or_default();
+            (key, value)
+        })
+        .collect();
+    (name, attrs)
+}
+
+fn for_each_element<F>(xml: &str, mut f: F)
+where
+    F: FnMut(&str, &HashMap<String, String>),
+{
+    for_each_event(xml, |event, name, attrs| {
+        if event == XmlEvent::Start {
+            f(name, attrs)
+        }
+    });
+}
@@ -0,0 +1,88 @@
+use markup5ever_rcdom::Handle;
+use url::Url;
+
+use crate::error::MarkitdownError;
+use crate::html::{
+    canonical_url, convert_site_bytes, convert_site_file, element_name, find_element, has_class,
+    has_id, meta_content, remove_elements, render_node, Site, SitePage,
+};
+use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+
+/// Converts saved GitHub repository pages into the repository name, its
+/// description and the rendered README.
+pub struct GithubConverter;
+
+pub(crate) const SITE: Site = Site {
+    page: "GitHub repository page",
+    detect: is_github_page,
+    render: render_readme,
+};
+
+impl DocumentConverter for GithubConverter {
+    fn convert(
+        &self,
+        local_path: &str,
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        convert_site_file(&SITE, local_path, &args)
+    }
+
+    fn convert_bytes(
+        &self,
+        bytes: &[u8],
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        convert_site_bytes(&SITE, bytes, &args)
+    }
+}
+
+/// The repository name and description, followed by the README.
+fn render_readme(
+    document: &Handle,
+    args: &Option<ConversionOptions>,
+) -> Result<SitePage, MarkitdownError> {
+    let readme = find_element(document, &|node| has_id(node, "readme"))
+        .and_then(|readme| find_element(&readme, &|node| has_class(node, "markdown-body")))
+        .ok_or_else(|| MarkitdownError::InvalidFile("Page has no README".to_string()))?;
+
+    // Copy buttons sit next to every code block.
+    remove_elements(&readme, &|node| {
+        has_class(node, "zeroclipboard-container") || element_name(node) == Some("clipboard-copy")
+    });
+
+    let title = meta_content(document, "octolytics-dimension-repository_nwo").or_else(|| {
+        let og_title = meta_content(document, "og:title")?;
+        let title = og_title.strip_prefix("GitHub - ").unwrap_or(&og_title);
+        Some(title.split(':').next().unwrap_or(title).trim().to_string())
+    });
+
+    let mut markdown = String::new();
+    if let Some(title) = &title {
+        markdown.push_str(&format!("# {}\n\n", title));
+    }
+    if let Some(description) = repository_description(document, title.as_deref()) {
+        markdown.push_str(&format!("{}\n\n", description));
+    }
+    markdown.push_str(&render_node(&readme, document, args));
+
+    Ok((title, markdown))
+}
+
+fn is_github_page(document: &Handle, page_url: Option<&str>) -> bool {
+    [page_url.map(str::to_string), canonical_url(document)]
+        .into_iter()
+        .flatten()
+        .filter_map(|url| Url::parse(&url).ok())
+        .any(|url| url.host_str() == Some("github.com"))
+}
+
+/// The description from the page title `GitHub - owner/repo: description`.
+fn repository_description(document: &Handle, name: Option<&str>) -> Option<String> {
+    let og_title = meta_content(document, "og:title")?;
+    let prefix = format!("GitHub - {}: ", name?);
+    og_title
+        .strip_prefix(&prefix)
+        .map(str::trim)
+        .filter(|description| !description.is_empty())
+        .map(str::to_string)
+}
@@ -1,8 +1,20 @@
-use html2md::parse_html;
+use std::collections::{BTreeMap, HashMap};
+use std::rc::Rc;
+
+use html5ever::serialize::{serialize, SerializeOpts, TraversalScope};
+use html5ever::tendril::TendrilSink;
+use html5ever::{parse_document, ParseOpts};
+use markup5ever_rcdom::{Handle, Node, NodeData, RcDom, SerializableHandle};
+use url::Url;
 
 use crate::error::MarkitdownError;
-use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+use crate::model::{
+    ConversionOptions, DocumentConverter, DocumentConverterResult, HeadingStyle, LinkMode,
+    MarkdownStyle,
+};
+use crate::{github, stackexchange, wikipedia};
 
+/// Converts HTML pages, applying the rules of the `SITES` to their pages.
 pub struct HtmlConverter;
 
 impl DocumentConverter for HtmlConverter {
@@ -22,11 +34,7 @@ impl DocumentConverter for HtmlConverter {
         }
 
         let content = std::fs::read_to_string(local_path)?;
-        let markdown = parse_html(&content);
-        Ok(DocumentConverterResult {
-            title: extract_title(&content),
-            text_content: markdown,
-        })
+        Ok(convert_html(&content, &kwargs))
     }
 
     fn convert_bytes(
@@ -46,20 +54,1257 @@ impl DocumentConverter for HtmlConverter {
 
         let content = String::from_utf8(bytes.to_vec())
             .map_err(|e| MarkitdownError::ParseError(format!("Invalid UTF-8 encoding: {}", e)))?;
-        let markdown = parse_html(&content);
-        Ok(DocumentConverterResult {
-            title: extract_title(&content),
-            text_content: markdown,
+        Ok(convert_html(&content, &kwargs))
+    }
+}
+
+fn convert_html(content: &str, options: &Option<ConversionOptions>) -> DocumentConverterResult {
+    let document = parse_html(content);
+    if let Some(result) = SITES
+        .iter()
+        .find_map(|site| convert_site_page(site, &document, options).ok())
+    {
+        return result;
+    }
+
+    // Read before rendering, which drops the `<head>`.
+    let metadata = extract_metadata(&document);
+    let title = metadata
+        .get("title")
+        .or_else(|| metadata.get("og:title"))
+        .cloned()
+        .or_else(|| first_heading(&document));
+    DocumentConverterResult {
+        title,
+        text_content: document_to_markdown(&document, options),
+        metadata,
+        ..Default::default()
+    }
+}
+
+/// A website whose saved pages are converted by picking out their content
+/// instead of rendering the whole page.
+pub(crate) struct Site {
+    /// What the site's pages are, for errors such as "Not a Wikipedia article".
+    pub(crate) page: &'static str,
+    /// Whether a parsed page, saved from the given URL, belongs to the site.
+    pub(crate) detect: fn(&Handle, Option<&str>) -> bool,
+    /// Renders a detected page as its title and markdown. Fails without
+    /// changing the page when the content can't be found, so the page can
+    /// still be rendered as plain HTML.
+    pub(crate) render: fn(&Handle, &Option<ConversionOptions>) -> Result<SitePage, MarkitdownError>,
+}
+
+/// The title and markdown of a page rendered by a `Site`.
+pub(crate) type SitePage = (Option<String>, String);
+
+/// Sites with their own conversion, tried in order before a page is
+/// rendered as plain HTML.
+pub(crate) const SITES: [&Site; 3] = [&github::SITE, &stackexchange::SITE, &wikipedia::SITE];
+
+/// Converts a page with the rules of `site`, failing when the page isn't
+/// one of the site's.
+pub(crate) fn convert_site_page(
+    site: &Site,
+    document: &Handle,
+    options: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    let mut options = options.clone().unwrap_or_default();
+    if !(site.detect)(document, options.url.as_deref()) {
+        return Err(MarkitdownError::InvalidFile(format!("Not a {}", site.page)));
+    }
+    // Saved pages link relative to where they were saved from.
+    if options.url.is_none() {
+        options.url = canonical_url(document);
+    }
+    let (title, markdown) = (site.render)(document, &Some(options))?;
+    Ok(DocumentConverterResult {
+        title,
+        text_content: markdown.trim_end().to_string(),
+        metadata: extract_metadata(document),
+        ..Default::default()
+    })
+}
+
+/// Converts an HTML file with the rules of one site, for the site converters.
+pub(crate) fn convert_site_file(
+    site: &Site,
+    local_path: &str,
+    options: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    check_html_extension(options)?;
+    let content = std::fs::read_to_string(local_path)?;
+    convert_site_page(site, &parse_html(&content), options)
+}
+
+/// Converts HTML bytes with the rules of one site, for the site converters.
+pub(crate) fn convert_site_bytes(
+    site: &Site,
+    bytes: &[u8],
+    options: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    check_html_extension(options)?;
+    let content = std::str::from_utf8(bytes)
+        .map_err(|e| MarkitdownError::ParseError(format!("Invalid UTF-8 encoding: {}", e)))?;
+    convert_site_page(site, &parse_html(content), options)
+}
+
+fn check_html_extension(options: &Option<ConversionOptions>) -> Result<(), MarkitdownError> {
+    match options.as_ref().and_then(|opts| opts.file_extension.as_deref()) {
+        Some(ext) if ext != ".html" && ext != ".htm" => Err(MarkitdownError::InvalidFile(
+            format!("Expected .html or .htm file, got {}", ext),
+        )),
+        _ => Ok(()),
+    }
+}
+
+/// Collects the page title and the `<meta>`/`<link>` properties describing
+/// the document. Keys are only present when the page provides a value.
+pub(crate) fn extract_metadata(document: &Handle) -> BTreeMap<String, String> {
+    let mut metadata = BTreeMap::new();
+    let mut insert = |key: &str, value: Option<String>| {
+        if let Some(value) = value.filter(|value| !value.is_empty()) {
+            metadata.entry(key.to_string()).or_insert(value);
+        }
+    };
+
+    // `<title>` elements inside inline SVG are in the SVG namespace.
+    let title = find_element(document, &|node| match &node.data {
+        NodeData::Element { name, .. } => &*name.ns == HTML_NAMESPACE && &*name.local == "title",
+        _ => false,
+    });
+    insert("title", title.map(|title| collapse_whitespace(&text_content(&title))));
+    insert("og:title", meta_content(document, "og:title"));
+    insert("description", meta_content(document, "description"));
+    insert("description", meta_content(document, "og:description"));
+    insert("canonical_url", canonical_url(document));
+    let root = find_element(document, &|node| element_name(node) == Some("html"));
+    insert("language", root.and_then(|root| attr(&root, "lang")));
+    insert("language", meta_content(document, "og:locale"));
+    insert("author", meta_content(document, "author"));
+    insert("author", meta_content(document, "article:author"));
+    for key in ["article:published_time", "datePublished", "date", "dc.date"] {
+        insert("published", meta_content(document, key));
+    }
+    let published = find_element(document, &|node| {
+        element_name(node) == Some("time")
+            && (attr(node, "itemprop").as_deref() == Some("datePublished")
+                || attr(node, "pubdate").is_some())
+    });
+    insert("published", published.and_then(|time| attr(&time, "datetime")));
+    metadata
+}
+
+fn first_heading(document: &Handle) -> Option<String> {
+    let heading = find_element(document, &|node| element_name(node) == Some("h1"))?;
+    Some(collapse_whitespace(&text_content(&heading))).filter(|text| !text.is_empty())
+}
+
+/// Elements whose content is never part of the rendered document.
+const NON_CONTENT_TAGS: [&str; 4] = ["script", "style", "noscript", "template"];
+
+/// Elements that hold page chrome rather than content in readability mode.
+const BOILERPLATE_TAGS: [&str; 7] = ["nav", "aside", "header", "footer", "form", "dialog", "menu"];
+
+/// Class and id fragments that mark page chrome.
+const NEGATIVE_HINTS: [&str; 24] = [
+    "nav", "menu", "footer", "header", "sidebar", "cookie", "consent", "banner", "comment",
+    "share", "social", "related", "promo", "advert", "breadcrumb", "popup", "modal",
+    "subscribe", "newsletter", "masthead", "toolbar", "pagination", "skip", "toc",
+];
+
+/// Class and id fragments that mark article content.
+const POSITIVE_HINTS: [&str; 10] = [
+    "article", "content", "main", "post", "entry", "body", "text", "story", "blog", "markdown",
+];
+
+/// Minimum text length for an element to be considered a content candidate.
+const MIN_CANDIDATE_TEXT: usize = 140;
+
+/// Namespace of HTML elements, as opposed to inline SVG and MathML.
+const HTML_NAMESPACE: &str = "http://www.w3.org/1999/xhtml";
+
+pub(crate) fn html_to_markdown(content: &str, options: &Option<ConversionOptions>) -> String {
+    document_to_markdown(&parse_html(content), options)
+}
+
+fn document_to_markdown(document: &Handle, options: &Option<ConversionOptions>) -> String {
+    let main_content = options.as_ref().is_some_and(|opts| opts.html_main_content);
+    let root = if main_content {
+        // Script text would otherwise count as content while scoring.
+        remove_elements(document, &is_non_content);
+        let root = find_main_content(document).unwrap_or_else(|| document.clone());
+        remove_elements(&root, &is_boilerplate);
+        root
+    } else {
+        document.clone()
+    };
+    render_node(&root, document, options)
+}
+
+pub(crate) fn parse_html(content: &str) -> Handle {
+    parse_document(RcDom::default(), ParseOpts::default())
+        .one(content)
+        .document
+}
+
+/// Renders `node`, an element of `document` or the document itself, as
+/// markdown. Links resolve against the page URL or the document's `<base>`.
+pub(crate) fn render_node(
+    node: &Handle,
+    document: &Handle,
+    options: &Option<ConversionOptions>,
+) -> String {
+    let page_url = options.as_ref().and_then(|opts| opts.url.as_deref());
+    let base = base_url(document, page_url);
+    remove_elements(node, &|node| {
+        is_non_content(node) || element_name(node) == Some("head")
+    });
+
+    let renderer = Renderer {
+        style: options
+            .as_ref()
+            .map(|opts| opts.html_style)
+            .unwrap_or_default(),
+        links: options
+            .as_ref()
+            .map(|opts| opts.html_links)
+            .unwrap_or_default(),
+        base,
+    };
+    let mut writer = BlockWriter::default();
+    if element_name(node).is_some() {
+        renderer.block(node, &mut writer);
+    } else {
+        renderer.blocks(node, &mut writer);
+    }
+    writer.finish().join("\n\n")
+}
+
+fn is_non_content(node: &Handle) -> bool {
+    element_name(node).is_some_and(|name| NON_CONTENT_TAGS.contains(&name))
+}
+
+/// Content of the first `<meta>` whose `name` or `property` is `key`.
+pub(crate) fn meta_content(document: &Handle, key: &str) -> Option<String> {
+    let meta = find_element(document, &|node| {
+        element_name(node) == Some("meta")
+            && (attr(node, "name").as_deref() == Some(key)
+                || attr(node, "property").as_deref() == Some(key))
+    })?;
+    attr(&meta, "content").map(|content| collapse_whitespace(&content))
+}
+
+/// First element in document order matching `predicate`.
+pub(crate) fn find_element(root: &Handle, predicate: &dyn Fn(&Handle) -> bool) -> Option<Handle> {
+    if predicate(root) {
+        return Some(root.clone());
+    }
+    root.children
+        .borrow()
+        .iter()
+        .find_map(|child| find_element(child, predicate))
+}
+
+/// All elements matching `predicate`, outermost only.
+pub(crate) fn find_elements(root: &Handle, predicate: &dyn Fn(&Handle) -> bool) -> Vec<Handle> {
+    if predicate(root) {
+        return vec![root.clone()];
+    }
+    root.children
+        .borrow()
+        .iter()
+        .flat_map(|child| find_elements(child, predicate))
+        .collect()
+}
+
+/// The page's own URL as saved in `<link rel="canonical">` or `og:url`.
+pub(crate) fn canonical_url(document: &Handle) -> Option<String> {
+    find_element(document, &|node| {
+        element_name(node) == Some("link") && attr(node, "rel").as_deref() == Some("canonical")
+    })
+    .and_then(|link| attr(&link, "href"))
+    .or_else(|| meta_content(document, "og:url"))
+}
+
+pub(crate) fn has_class(node: &Handle, class: &str) -> bool {
+    attr(node, "class").is_some_and(|classes| classes.split_whitespace().any(|c| c == class))
+}
+
+pub(crate) fn has_id(node: &Handle, id: &str) -> bool {
+    attr(node, "id").as_deref() == Some(id)
+}
+
+/// The URL relative links are resolved against: the document's `<base href>`,
+/// itself relative to the page URL, or else the page URL.
+fn base_url(document: &Handle, page_url: Option<&str>) -> Option<Url> {
+    let page_url = page_url.and_then(|url| Url::parse(url.trim()).ok());
+    let mut base_href = None;
+    walk(document, &mut |node| {
+        if base_href.is_none() && element_name(node) == Some("base") {
+            base_href = attr(node, "href");
+        }
+    });
+    let base = base_href.and_then(|href| match &page_url {
+        Some(page_url) => page_url.join(href.trim()).ok(),
+        None => Url::parse(href.trim()).ok(),
+    });
+    base.or(page_url)
+}
+
+/// Picks the element holding the main content of the page: the best scoring
+/// `<article>`/`<main>` landmark when there is one, otherwise the ancestor
+/// collecting the most paragraph text, readability style.
+fn find_main_content(document: &Handle) -> Option<Handle> {
+    let mut landmarks = Vec::new();
+    let mut articles = Vec::new();
+    walk(document, &mut |node| {
+        let Some(name) = element_name(node) else {
+            return;
+        };
+        let is_landmark = matches!(name, "article" | "main")
+            || attr(node, "role").as_deref() == Some("main")
+            || attr(node, "itemprop").as_deref() == Some("articleBody");
+        if is_landmark && text_stats(node).0 >= MIN_CANDIDATE_TEXT {
+            if name == "article" {
+                articles.push(node.clone());
+            }
+            landmarks.push(node.clone());
+        }
+    });
+
+    // Listing pages hold one `<article>` per teaser; keep them all together.
+    if articles.len() > 1 {
+        let mut scores: Vec<f64> = articles.iter().map(content_score).collect();
+        scores.sort_by(|a, b| b.total_cmp(a));
+        if scores[1] * 2.0 >= scores[0] {
+            return articles
+                .iter()
+                .skip(1)
+                .try_fold(articles[0].clone(), |ancestor, article| {
+                    common_ancestor(&ancestor, article)
+                });
+        }
+    }
+    if let Some(best) = landmarks
+        .iter()
+        .max_by(|a, b| content_score(a).total_cmp(&content_score(b)))
+    {
+        // Prefer an enclosing landmark, e.g. the `<article>` around an
+        // `articleBody`, as long as it adds little besides its header.
+        let score = content_score(best);
+        let mut enclosing = landmarks.iter().filter(|landmark| {
+            !Rc::ptr_eq(landmark, best)
+                && is_ancestor(landmark, best)
+                && content_score(landmark) >= score * 0.9
+        });
+        return Some(enclosing.next().unwrap_or(best).clone());
+    }
+
+    score_paragraph_parents(document)
+}
+
+/// Distributes the score of every paragraph-like element to its parent and,
+/// halved, to its grandparent, then returns the best scoring ancestor.
+fn score_paragraph_parents(document: &Handle) -> Option<Handle> {
+    let mut scores: HashMap<*const Node, (Handle, f64)> = HashMap::new();
+    walk(document, &mut |node| {
+        if !matches!(element_name(node), Some("p" | "pre" | "td" | "blockquote")) {
+            return;
+        }
+        let text = collapse_whitespace(&text_content(node));
+        let length = text.chars().count();
+        if length < 25 {
+            return;
+        }
+        let score = 1.0 + text.matches(',').count() as f64 + (length / 100).min(3) as f64;
+        let ancestors = parent(node).into_iter().flat_map(|p| {
+            let grandparent = parent(&p);
+            std::iter::once((p, score)).chain(grandparent.map(|g| (g, score / 2.0)))
+        });
+        for (ancestor, share) in ancestors {
+            if element_name(&ancestor).is_none() {
+                continue;
+            }
+            scores
+                .entry(Rc::as_ptr(&ancestor))
+                .or_insert_with(|| {
+                    let initial = tag_score(&ancestor) + class_weight(&ancestor);
+                    (ancestor.clone(), initial)
+                })
+                .1 += share;
+        }
+    });
+
+    scores
+        .into_values()
+        .map(|(node, score)| {
+            let (_, link_density) = text_stats(&node);
+            (node, score * (1.0 - link_density))
         })
+        .max_by(|a, b| a.1.total_cmp(&b.1))
+        .filter(|(_, score)| *score > 0.0)
+        .map(|(node, _)| node)
+}
+
+fn content_score(node: &Handle) -> f64 {
+    let (length, link_density) = text_stats(node);
+    (length as f64 + class_weight(node) * 10.0) * (1.0 - link_density)
+}
+
+fn tag_score(node: &Handle) -> f64 {
+    match element_name(node) {
+        Some("div" | "article" | "main" | "section") => 5.0,
+        Some("pre" | "td" | "blockquote") => 3.0,
+        Some("form" | "ul" | "ol" | "dl" | "li" | "address") => -3.0,
+        Some("h1" | "h2" | "h3" | "h4" | "h5" | "h6" | "th") => -5.0,
+        _ => 0.0,
+    }
+}
+
+/// Scores the `class` and `id` of an element by how much they sound like
+/// content rather than page chrome.
+fn class_weight(node: &Handle) -> f64 {
+    let words = hint_words(node);
+    let mut weight = 0.0;
+    if has_hint(&words, &NEGATIVE_HINTS) {
+        weight -= 25.0;
+    }
+    if has_hint(&words, &POSITIVE_HINTS) {
+        weight += 25.0;
+    }
+    weight
+}
+
+/// Splits `class` and `id` into lowercase words, so that `blog-sidebar`,
+/// `blog_sidebar` and `blogSidebar` all yield `blog` and `sidebar`.
+fn hint_words(node: &Handle) -> Vec<String> {
+    let mut words = Vec::new();
+    for value in ["class", "id"].iter().filter_map(|name| attr(node, name)) {
+        let mut word = String::new();
+        let mut previous_lower = false;
+        for c in value.chars() {
+            let boundary = !c.is_alphanumeric() || (c.is_uppercase() && previous_lower);
+            if boundary && !word.is_empty() {
+                words.push(std::mem::take(&mut word));
+            }
+            if c.is_alphanumeric() {
+                word.extend(c.to_lowercase());
+            }
+            previous_lower = c.is_lowercase();
+        }
+        if !word.is_empty() {
+            words.push(word);
+        }
+    }
+    words
+}
+
+fn has_hint(words: &[String], hints: &[&str]) -> bool {
+    words
+        .iter()
+        .any(|word| hints.iter().any(|hint| word.starts_with(hint)))
+}
+
+/// Page chrome left inside the main content, e.g. share bars and tag lists.
+fn is_boilerplate(node: &Handle) -> bool {
+    let Some(name) = element_name(node) else {
+        return false;
+    };
+    if attr(node, "hidden").is_some()
+        || attr(node, "aria-hidden").as_deref() == Some("true")
+        || attr(node, "style").is_some_and(|style| {
+            let style = style.replace(' ', "").to_ascii_lowercase();
+            style.contains("display:none") || style.contains("visibility:hidden")
+        })
+    {
+        return true;
+    }
+    if BOILERPLATE_TAGS.contains(&name) {
+        // Article headers usually carry the title and byline.
+        return !(name == "header" && contains_element(node, &["h1", "h2"]));
+    }
+    // Only generic containers are judged by their class names; headings,
+    // paragraphs and the like are always kept.
+    if !matches!(name, "div" | "section" | "ul" | "ol" | "dl" | "span") {
+        return false;
+    }
+    let words = hint_words(node);
+    has_hint(&words, &NEGATIVE_HINTS)
+        && !has_hint(&words, &POSITIVE_HINTS)
+        && !contains_element(node, &["article", "main"])
+}
+
+/// Returns the text length of a node and the share of it inside links.
+fn text_stats(node: &Handle) -> (usize, f64) {
+    fn visit(node: &Handle, in_link: bool, total: &mut usize, linked: &mut usize) {
+        match &node.data {
+            NodeData::Text { contents } => {
+                let length = collapse_whitespace(&contents.borrow()).chars().count();
+                *total += length;
+                if in_link {
+                    *linked += length;
+                }
+            }
+            _ => {
+                let in_link = in_link || element_name(node) == Some("a");
+                for child in node.children.borrow().iter() {
+                    visit(child, in_link, total, linked);
+                }
+            }
+        }
     }
+
+    let (mut total, mut linked) = (0, 0);
+    visit(node, false, &mut total, &mut linked);
+    let density = if total == 0 {
+        0.0
+    } else {
+        linked as f64 / total as f64
+    };
+    (total, density)
+}
+
+pub(crate) fn text_content(node: &Handle) -> String {
+    let mut text = String::new();
+    walk(node, &mut |node| {
+        if let NodeData::Text { contents } = &node.data {
+            text.push_str(&contents.borrow());
+        }
+    });
+    text
 }
 
-fn extract_title(html: &str) -> Option<String> {
-    use regex::Regex;
+pub(crate) fn collapse_whitespace(text: &str) -> String {
+    text.split_whitespace().collect::<Vec<_>>().join(" ")
+}
 
-    let re = Regex::new(r"(?i)<title(?:\s[^>]*)?>(.*?)</title>").ok()?;
-    if let Some(captures) = re.captures(html) {
-        return Some(captures[1].trim().to_string());
+pub(crate) fn element_name(node: &Handle) -> Option<&str> {
+    match &node.data {
+        NodeData::Element { name, .. } => Some(&name.local),
+        _ => None,
+    }
+}
+
+pub(crate) fn attr(node: &Handle, attr_name: &str) -> Option<String> {
+    match &node.data {
+        NodeData::Element { attrs, .. } => attrs
+            .borrow()
+            .iter()
+            .find(|attr| &*attr.name.local == attr_name)
+            .map(|attr| attr.value.to_string()),
+        _ => None,
+    }
+}
+
+fn contains_element(node: &Handle, names: &[&str]) -> bool {
+    node.children.borrow().iter().any(|child| {
+        element_name(child).is_some_and(|name| names.contains(&name))
+            || contains_element(child, names)
+    })
+}
+
+/// Visits `node` and all of its descendants in document order.
+pub(crate) fn walk(node: &Handle, visit: &mut impl FnMut(&Handle)) {
+    visit(node);
+    for child in node.children.borrow().iter() {
+        walk(child, visit);
+    }
+}
+
+/// Detaches every descendant of `node` matching `predicate`.
+pub(crate) fn remove_elements(node: &Handle, predicate: &dyn Fn(&Handle) -> bool) {
+    node.children.borrow_mut().retain(|child| !predicate(child));
+    for child in node.children.borrow().iter() {
+        remove_elements(child, predicate);
+    }
+}
+
+fn parent(node: &Handle) -> Option<Handle> {
+    let weak = node.parent.take();
+    let parent = weak.as_ref().and_then(|weak| weak.upgrade());
+    node.parent.set(weak);
+    parent
+}
+
+fn is_ancestor(ancestor: &Handle, node: &Handle) -> bool {
+    let mut current = parent(node);
+    while let Some(node) = current {
+        if Rc::ptr_eq(&node, ancestor) {
+            return true;
+        }
+        current = parent(&node);
+    }
+    false
+}
+
+fn common_ancestor(a: &Handle, b: &Handle) -> Option<Handle> {
+    let mut ancestors = vec![a.clone()];
+    while let Some(next) = parent(ancestors.last()?) {
+        ancestors.push(next);
+    }
+    let mut current = Some(b.clone());
+    while let Some(node) = current {
+        if ancestors.iter().any(|ancestor| Rc::ptr_eq(ancestor, &node)) {
+            return Some(node);
+        }
+        current = parent(&node);
     }
     None
 }
+
+pub(crate) fn serialize_node(node: &Handle) -> String {
+    let scope = if element_name(node).is_some() {
+        TraversalScope::IncludeNode
+    } else {
+        TraversalScope::ChildrenOnly(None)
+    };
+    let opts = SerializeOpts {
+        traversal_scope: scope,
+        ..Default::default()
+    };
+    let mut html = Vec::new();
+    let handle: SerializableHandle = node.clone().into();
+    if serialize(&mut html, &handle, opts).is_err() {
+        return String::new();
+    }
+    String::from_utf8_lossy(&html).into_owned()
+}
+
+/// Elements that start a new block in the rendered markdown.
+const BLOCK_TAGS: [&str; 40] = [
+    "address", "article", "aside", "blockquote", "body", "center", "dd", "details", "dialog",
+    "div", "dl", "dt", "fieldset", "figcaption", "figure", "footer", "form", "h1", "h2", "h3",
+    "h4", "h5", "h6", "header", "hgroup", "hr", "html", "li", "main", "menu", "nav", "ol", "p",
+    "pre", "section", "summary", "table", "tr", "ul", "caption",
+];
+
+/// Collects rendered blocks, buffering inline content until the next block.
+#[derive(Default)]
+struct BlockWriter {
+    blocks: Vec<String>,
+    inline: String,
+}
+
+impl BlockWriter {
+    fn push(&mut self, block: String) {
+        self.flush();
+        if !block.trim().is_empty() {
+            self.blocks.push(block);
+        }
+    }
+
+    /// Turns buffered inline content into a paragraph. Hard line breaks are
+    /// kept as `\n` in the buffer until here.
+    fn flush(&mut self) {
+        let paragraph = tidy_inline(&std::mem::take(&mut self.inline));
+        if !paragraph.is_empty() {
+            self.blocks.push(paragraph);
+        }
+    }
+
+    fn finish(mut self) -> Vec<String> {
+        self.flush();
+        self.blocks
+    }
+}
+
+struct Renderer {
+    style: MarkdownStyle,
+    links: LinkMode,
+    base: Option<Url>,
+}
+
+impl Renderer {
+    /// Renders the children of `node` as a sequence of blocks.
+    fn blocks(&self, node: &Handle, writer: &mut BlockWriter) {
+        for child in node.children.borrow().iter() {
+            self.block(child, writer);
+        }
+    }
+
+    fn block(&self, node: &Handle, writer: &mut BlockWriter) {
+        let Some(name) = element_name(node) else {
+            self.inline(node, &mut writer.inline);
+            return;
+        };
+        match name {
+            "h1" | "h2" | "h3" | "h4" | "h5" | "h6" => {
+                let level = name[1..].parse().unwrap_or(1);
+                let text = self.inline_text(node);
+                if !text.is_empty() {
+                    writer.push(self.heading(level, &text));
+                }
+            }
+            "hr" => writer.push("---".to_string()),
+            "pre" => writer.push(code_block(node)),
+            "blockquote" => {
+                let inner = self.render_blocks(node).join("\n\n");
+                let quoted: Vec<String> = inner
+                    .lines()
+                    .map(|line| {
+                        if line.is_empty() {
+                            ">".to_string()
+                        } else {
+                            format!("> {}", line)
+                        }
+                    })
+                    .collect();
+                writer.push(quoted.join("\n"));
+            }
+            "ul" | "ol" | "menu" => writer.push(self.list(node)),
+            "dl" => writer.push(self.definition_list(node)),
+            "table" => writer.push(self.table(node)),
+            "figure" => {
+                writer.flush();
+                for child in node.children.borrow().iter() {
+                    if element_name(child) == Some("figcaption") {
+                        let caption = self.inline_text(child);
+                        if !caption.is_empty() {
+                            writer.push(self.emphasize(&caption, 1));
+                        }
+                    } else {
+                        self.block(child, writer);
+                    }
+                }
+                writer.flush();
+            }
+            _ if BLOCK_TAGS.contains(&name) || (name != "a" && contains_block(node)) => {
+                writer.flush();
+                self.blocks(node, writer);
+                writer.flush();
+            }
+            _ => self.inline(node, &mut writer.inline),
+        }
+    }
+
+    fn render_blocks(&self, node: &Handle) -> Vec<String> {
+        let mut writer = BlockWriter::default();
+        self.blocks(node, &mut writer);
+        writer.finish()
+    }
+
+    fn heading(&self, level: usize, text: &str) -> String {
+        let text = text.replace("  \n", " ");
+        match self.style.heading {
+            HeadingStyle::Setext if level <= 2 => {
+                let underline = if level == 1 { "=" } else { "-" };
+                format!("{}\n{}", text, underline.repeat(text.chars().count().max(3)))
+            }
+            _ => format!("{} {}", "#".repeat(level), text),
+        }
+    }
+
+    fn list(&self, node: &Handle) -> String {
+        let ordered = element_name(node) == Some("ol");
+        let mut number = attr(node, "start")
+            .and_then(|start| start.trim().parse::<i64>().ok())
+            .unwrap_or(1);
+        let mut items = Vec::new();
+        for child in node.children.borrow().iter() {
+            if element_name(child) != Some("li") {
+                // Stray content between items, e.g. a nested list without
+                // its own `<li>`, is kept with the previous item.
+                let rendered = self.render_blocks_of(child);
+                if let (Some(last), false) = (items.last_mut(), rendered.is_empty()) {
+                    let last: &mut String = last;
+                    last.push('\n');
+                    last.push_str(&indent(&format!("\n{}", rendered), 2)[1..]);
+                }
+                continue;
+            }
+            let marker = if ordered {
+                let marker = format!("{}.", number);
+                number += 1;
+                marker
+            } else {
+                self.style.bullet.to_string()
+            };
+            let mut blocks = self.render_blocks(child);
+            if let Some(checkbox) = task_marker(child) {
+                match blocks.first_mut() {
+                    Some(first) => first.insert_str(0, checkbox),
+                    None => blocks.push(checkbox.trim_end().to_string()),
+                }
+            }
+            // Items made of a line of text plus nested lists stay tight.
+            let loose = has_child_element(child, &["p", "pre", "blockquote", "table", "div"]);
+            let content = blocks.join(if loose { "\n\n" } else { "\n" });
+            let content = indent(&content, marker.chars().count() + 1);
+            items.push(format!("{} {}", marker, content).trim_end().to_string());
+        }
+        items.join("\n")
+    }
+
+    fn render_blocks_of(&self, node: &Handle) -> String {
+        let mut writer = BlockWriter::default();
+        self.block(node, &mut writer);
+        writer.finish().join("\n\n")
+    }
+
+    /// Renders `<dl>` the way pandoc and PHP Markdown Extra write definition
+    /// lists: the term on its own line and each definition after `: `.
+    fn definition_list(&self, node: &Handle) -> String {
+        let mut groups: Vec<Vec<String>> = Vec::new();
+        let mut in_definitions = false;
+        let mut visit = |child: &Handle| match element_name(child) {
+            Some("dt") => {
+                let term = self.inline_text(child).replace("  \n", " ");
+                if in_definitions || groups.is_empty() {
+                    groups.push(Vec::new());
+                }
+                in_definitions = false;
+                if let Some(group) = groups.last_mut() {
+                    group.push(term);
+                }
+            }
+            Some("dd") => {
+                let definition = self.render_blocks(child).join("\n\n");
+                if groups.is_empty() {
+                    groups.push(Vec::new());
+                }
+                in_definitions = true;
+                if let Some(group) = groups.last_mut() {
+                    group.push(format!(": {}", indent(&definition, 2)));
+                }
+            }
+            _ => {}
+        };
+        for child in node.children.borrow().iter() {
+            // `<div>` may wrap each term/definition group.
+            if element_name(child) == Some("div") {
+                for grandchild in child.children.borrow().iter() {
+                    visit(grandchild);
+                }
+            } else {
+                visit(child);
+            }
+        }
+        groups
+            .iter()
+            .map(|group| group.join("\n"))
+            .collect::<Vec<_>>()
+            .join("\n\n")
+    }
+
+    /// Renders a GFM table, or the original HTML when the table uses
+    /// features markdown cannot express, like spans or block content.
+    fn table(&self, node: &Handle) -> String {
+        if is_complex_table(node) {
+            return serialize_node(node).trim().to_string();
+        }
+
+        let mut caption = None;
+        let mut rows: Vec<Vec<Handle>> = Vec::new();
+        let mut push_row = |row: &Handle| {
+            let cells = row
+                .children
+                .borrow()
+                .iter()
+                .filter(|cell| matches!(element_name(cell), Some("td" | "th")))
+                .cloned()
+                .collect();
+            rows.push(cells);
+        };
+        for child in node.children.borrow().iter() {
+            match element_name(child) {
+                Some("caption") => caption = Some(self.inline_text(child)),
+                Some("thead" | "tbody" | "tfoot") => {
+                    for row in child.children.borrow().iter() {
+                        if element_name(row) == Some("tr") {
+                            push_row(row);
+                        }
+                    }
+                }
+                Some("tr") => push_row(child),
+                _ => {}
+            }
+        }
+
+        let width = rows.iter().map(Vec::len).max().unwrap_or(0);
+        if width == 0 {
+            return String::new();
+        }
+        let render_row = |cells: &[Handle]| {
+            let mut line = String::from("|");
+            for col in 0..width {
+                let text = cells
+                    .get(col)
+                    .map(|cell| self.inline_text(cell))
+                    .unwrap_or_default();
+                let text = text.replace('|', "\\|").replace("  \n", "<br>");
+                line.push_str(&format!(" {} |", text));
+            }
+            line
+        };
+
+        // GFM tables always have a header, so a table without `<th>` cells
+        // uses its first row.
+        let (header, body) = (&rows[0], &rows[1..]);
+        let mut lines = vec![render_row(header)];
+        let mut delimiter = String::from("|");
+        for col in 0..width {
+            let align = header.get(col).and_then(cell_alignment).or_else(|| {
+                body.first()
+                    .and_then(|cells| cells.get(col))
+                    .and_then(cell_alignment)
+            });
+            delimiter.push_str(match align.as_deref() {
+                Some("left") => " :--- |",
+                Some("center") => " :---: |",
+                Some("right") => " ---: |",
+                _ => " --- |",
+            });
+        }
+        lines.push(delimiter);
+        for cells in body {
+            lines.push(render_row(cells));
+        }
+
+        let table = lines.join("\n");
+        match caption.filter(|caption| !caption.is_empty()) {
+            Some(caption) => format!("{}\n\n{}", caption, table),
+            None => table,
+        }
+    }
+
+    /// Renders `node` as a single line of inline markdown.
+    fn inline_text(&self, node: &Handle) -> String {
+        tidy_inline(&self.inline_children(node))
+    }
+
+    fn inline_children(&self, node: &Handle) -> String {
+        let mut text = String::new();
+        for child in node.children.borrow().iter() {
+            self.inline(child, &mut text);
+        }
+        text
+    }
+
+    /// Renders the inline content of `node` through `render`, keeping the
+    /// whitespace around it outside so that delimiters stay valid and words
+    /// stay apart exactly as in the source.
+    fn wrap_inline(&self, node: &Handle, out: &mut String, render: impl Fn(&str) -> String) {
+        let raw = self.inline_children(node);
+        let text = tidy_inline(&raw);
+        if raw.starts_with([' ', '\n']) {
+            out.push(' ');
+        }
+        if !text.is_empty() {
+            out.push_str(&render(&text));
+            if raw.ends_with([' ', '\n']) {
+                out.push(' ');
+            }
+        }
+    }
+
+    fn inline(&self, node: &Handle, out: &mut String) {
+        let name = match &node.data {
+            NodeData::Text { contents } => {
+                out.push_str(&escape_markdown(&collapse_text(&contents.borrow())));
+                return;
+            }
+            NodeData::Element { name, .. } => &*name.local,
+            _ => return,
+        };
+        match name {
+            "br" => out.push('\n'),
+            "img" => {
+                if let Some(src) = attr(node, "src") {
+                    let alt = attr(node, "alt").unwrap_or_default();
+                    out.push_str(&format!(
+                        "![{}]({}{})",
+                        escape_markdown(&collapse_whitespace(&alt)),
+                        link_destination(&self.resolve(&src)),
+                        link_title(node)
+                    ));
+                }
+            }
+            "a" => {
+                let href = attr(node, "href")
+                    .filter(|href| !href.trim_start().starts_with("javascript:"))
+                    .filter(|_| self.links == LinkMode::Keep);
+                self.wrap_inline(node, out, |text| {
+                    let text = text.replace("  \n", " ");
+                    let visible =
+                        text.trim_matches(|c: char| c.is_whitespace() || c == '\u{200b}');
+                    match &href {
+                        // Permalink anchors next to headings carry no text.
+                        _ if visible.is_empty() => String::new(),
+                        Some(href) => format!(
+                            "[{}]({}{})",
+                            text,
+                            link_destination(&self.resolve(href)),
+                            link_title(node)
+                        ),
+                        None => text,
+                    }
+                });
+            }
+            "strong" | "b" => self.wrap_inline(node, out, |text| self.emphasize(text, 2)),
+            "em" | "i" | "cite" | "dfn" => {
+                self.wrap_inline(node, out, |text| self.emphasize(text, 1))
+            }
+            "del" | "s" | "strike" => self.wrap_inline(node, out, |text| format!("~~{}~~", text)),
+            "code" | "kbd" | "samp" | "tt" => {
+                let code = collapse_text(&text_content(node));
+                if code.starts_with(' ') {
+                    out.push(' ');
+                }
+                if !code.trim().is_empty() {
+                    // Back to back spans would merge into one.
+                    if out.ends_with('`') {
+                        out.push(' ');
+                    }
+                    out.push_str(&code_span(code.trim()));
+                    if code.ends_with(' ') {
+                        out.push(' ');
+                    }
+                }
+            }
+            "input" if attr(node, "type").as_deref() == Some("checkbox") => {}
+            _ => {
+                let is_block = BLOCK_TAGS.contains(&name) || matches!(name, "td" | "th");
+                if is_block {
+                    out.push(' ');
+                }
+                for child in node.children.borrow().iter() {
+                    self.inline(child, out);
+                }
+                if is_block {
+                    out.push(' ');
+                }
+            }
+        }
+    }
+
+    /// Makes a relative URL absolute against the base URL. Fragment-only
+    /// links point into the document itself and are kept as they are.
+    fn resolve(&self, url: &str) -> String {
+        let url = url.trim();
+        match &self.base {
+            Some(base) if !url.starts_with('#') && Url::parse(url).is_err() => base
+                .join(url)
+                .map(String::from)
+                .unwrap_or_else(|_| url.to_string()),
+            _ => url.to_string(),
+        }
+    }
+
+    fn emphasize(&self, text: &str, strength: usize) -> String {
+        let delimiter = self.style.emphasis.to_string().repeat(strength);
+        format!("{}{}{}", delimiter, text, delimiter)
+    }
+}
+
+/// Renders `<pre>` as a fenced code block, taking the language from a
+/// `language-x` or `lang-x` class on the block or its `<code>`, or from the
+/// `highlight-source-x` wrapper GitHub puts around highlighted blocks.
+fn code_block(node: &Handle) -> String {
+    let mut language = code_language(node);
+    if language.is_none() {
+        for child in node.children.borrow().iter() {
+            if element_name(child) == Some("code") {
+                language = code_language(child);
+            }
+        }
+    }
+    if language.is_none() {
+        language = parent(node).and_then(|parent| code_language(&parent));
+    }
+    let mut code = String::new();
+    walk(node, &mut |node| match &node.data {
+        NodeData::Text { contents } => code.push_str(&contents.borrow()),
+        NodeData::Element { name, .. } if &*name.local == "br" => code.push('\n'),
+        _ => {}
+    });
+    let code = code.trim_end_matches(['\n', '\r']);
+    let longest = longest_run(code, '`');
+    let fence = "`".repeat(longest.max(2) + 1);
+    format!("{}{}\n{}\n{}", fence, language.unwrap_or_default(), code, fence)
+}
+
+fn code_language(node: &Handle) -> Option<String> {
+    let class = attr(node, "class")?;
+    class.split_whitespace().find_map(|class| {
+        class
+            .strip_prefix("language-")
+            .or_else(|| class.strip_prefix("lang-"))
+            .or_else(|| class.strip_prefix("highlight-source-"))
+            .filter(|language| !language.is_empty())
+            .map(str::to_string)
+    })
+}
+
+fn code_span(code: &str) -> String {
+    let fence = "`".repeat(longest_run(code, '`') + 1);
+    if code.starts_with('`') || code.ends_with('`') {
+        format!("{} {} {}", fence, code, fence)
+    } else {
+        format!("{}{}{}", fence, code, fence)
+    }
+}
+
+fn longest_run(text: &str, needle: char) -> usize {
+    let (mut longest, mut current) = (0, 0);
+    for c in text.chars() {
+        if c == needle {
+            current += 1;
+            longest = longest.max(current);
+        } else {
+            current = 0;
+        }
+    }
+    longest
+}
+
+/// A table needs raw HTML when cells span rows or columns, or hold blocks
+/// such as lists, code or nested tables that a GFM cell cannot contain.
+fn is_complex_table(table: &Handle) -> bool {
+    let mut complex = false;
+    for child in table.children.borrow().iter() {
+        walk(child, &mut |node| match element_name(node) {
+            Some("td" | "th") => {
+                let spans = ["colspan", "rowspan"].iter().any(|name| {
+                    attr(node, name)
+                        .and_then(|span| span.trim().parse::<u32>().ok())
+                        .is_some_and(|span| span > 1)
+                });
+                complex |= spans;
+            }
+            Some("table" | "ul" | "ol" | "pre" | "blockquote" | "dl" | "h1" | "h2" | "h3"
+            | "h4" | "h5" | "h6") => complex = true,
+            _ => {}
+        });
+    }
+    complex
+}
+
+fn cell_alignment(cell: &Handle) -> Option<String> {
+    if let Some(align) = attr(cell, "align") {
+        return Some(align.trim().to_ascii_lowercase());
+    }
+    let style = attr(cell, "style")?.to_ascii_lowercase();
+    style.split(';').find_map(|declaration| {
+        let (property, value) = declaration.split_once(':')?;
+        (property.trim() == "text-align").then(|| value.trim().to_string())
+    })
+}
+
+fn task_marker(item: &Handle) -> Option<&'static str> {
+    let mut marker = None;
+    walk(item, &mut |node| {
+        if marker.is_none()
+            && element_name(node) == Some("input")
+            && attr(node, "type").as_deref() == Some("checkbox")
+        {
+            marker = Some(if attr(node, "checked").is_some() {
+                "[x] "
+            } else {
+                "[ ] "
+            });
+        }
+    });
+    marker
+}
+
+fn contains_block(node: &Handle) -> bool {
+    node.children.borrow().iter().any(|child| {
+        element_name(child).is_some_and(|name| BLOCK_TAGS.contains(&name)) || contains_block(child)
+    })
+}
+
+fn has_child_element(node: &Handle, names: &[&str]) -> bool {
+    node.children
+        .borrow()
+        .iter()
+        .any(|child| element_name(child).is_some_and(|name| names.contains(&name)))
+}
+
+/// Indents every line but the first by `width` spaces, lining continuation
+/// lines up with the text after a list marker.
+fn indent(text: &str, width: usize) -> String {
+    let padding = " ".repeat(width);
+    let mut lines = text.lines();
+    let mut indented = lines.next().unwrap_or_default().to_string();
+    for line in lines {
+        indented.push('\n');
+        if !line.is_empty() {
+            indented.push_str(&padding);
+            indented.push_str(line);
+        }
+    }
+    indented
+}
+
+fn link_destination(url: &str) -> String {
+    let url = url.trim();
+    if url.contains([' ', '(', ')']) {
+        format!("<{}>", url.replace('<', "%3C").replace('>', "%3E"))
+    } else {
+        url.to_string()
+    }
+}
+
+fn link_title(node: &Handle) -> String {
+    match attr(node, "title").map(|title| collapse_whitespace(&title)) {
+        Some(title) if !title.is_empty() => format!(" \"{}\"", title.replace('"', "\\\"")),
+        _ => String::new(),
+    }
+}
+
+/// Collapses runs of HTML whitespace into single spaces, keeping a space at
+/// either end so adjacent inline nodes stay separated.
+fn collapse_text(text: &str) -> String {
+    let mut collapsed = String::with_capacity(text.len());
+    let mut in_space = false;
+    for c in text.chars() {
+        if c.is_ascii_whitespace() {
+            if !in_space {
+                collapsed.push(' ');
+            }
+            in_space = true;
+        } else {
+            collapsed.push(c);
+            in_space = false;
+        }
+    }
+    collapsed
+}
+
+/// Trims rendered inline content and turns the `\n` left by `<br>` into
+/// markdown hard line breaks.
+fn tidy_inline(text: &str) -> String {
+    let lines: Vec<String> = text
+        .split('\n')
+        .map(|line| collapse_spaces(line.trim()))
+        .collect();
+    let start = lines.iter().position(|line| !line.is_empty());
+    let end = lines.iter().rposition(|line| !line.is_empty());
+    match (start, end) {
+        (Some(start), Some(end)) => lines[start..=end].join("  \n"),
+        _ => String::new(),
+    }
+}
+
+fn collapse_spaces(line: &str) -> String {
+    let mut collapsed = String::with_capacity(line.len());
+    for c in line.chars() {
+        if c == ' ' && collapsed.ends_with(' ') {
+            continue;
+        }
+        collapsed.push(c);
+    }
+    collapsed
+}
+
+fn escape_markdown(text: &str) -> String {
+    let mut escaped = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '*' | '_' | '`' | '[' | ']') {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
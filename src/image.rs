@@ -1,12 +1,24 @@
 use crate::error::MarkitdownError;
-use crate::llm;
-use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
-use exif::Reader;
+use crate::llm::{self, CaptionRequest};
+use crate::model::{
+    ConversionOptions, ConversionWarning, DocumentConverter, DocumentConverterResult, OcrLine,
+    WarningKind,
+};
+use crate::ocr::{OcrEngine, TesseractOcr};
+use exif::{Exif, In, Reader, Tag, Value};
+use quick_xml::events::{BytesStart, Event};
+use quick_xml::Decoder;
+use std::collections::BTreeMap;
 use std::fs;
-use std::fs::File;
-use std::io::{BufReader, Cursor};
+use std::io::Cursor;
+use std::path::Path;
 use tokio;
 
+/// Extensions of the raster formats the converter reads.
+const IMAGE_EXTENSIONS: [&str; 9] = [
+    ".jpg", ".jpeg", ".png", ".gif", ".webp", ".tif", ".tiff", ".heic", ".heif",
+];
+
 pub struct ImageConverter;
 
 impl DocumentConverter for ImageConverter {
@@ -17,54 +29,20 @@ impl DocumentConverter for ImageConverter {
     ) -> Result<DocumentConverterResult, MarkitdownError> {
         if let Some(opts) = &args {
             if let Some(ext) = &opts.file_extension {
-                if ext != ".jpg" {
+                if !IMAGE_EXTENSIONS.contains(&ext.as_str()) {
                     return Err(MarkitdownError::InvalidFile(
-                        format!("Expected .jpg file, got {}", ext)
+                        format!("Expected an image file, got {}", ext)
                     ));
                 }
             }
         }
 
-        fs::metadata(local_path)?;
-
-        let file = File::open(local_path)?;
-        let exif = Reader::new().read_from_container(&mut BufReader::new(&file))
-            .map_err(|e| MarkitdownError::ParseError(format!("Failed to read EXIF data: {}", e)))?;
-
-        let mut markdown = String::new();
-
-        for field in exif.fields() {
-            markdown.push_str(&format!(
-                "{}: {}\n",
-                field.tag,
-                field.display_value().with_unit(&exif)
-            ));
-        }
-
-        if let Some(opts) = &args {
-            if let Some(llm_client) = &opts.llm_client {
-                if let Some(llm_model) = &opts.llm_model {
-                    let rt = tokio::runtime::Builder::new_current_thread()
-                        .enable_all()
-                        .build()
-                        .map_err(|e| MarkitdownError::Conversion(format!("Failed to create runtime: {}", e)))?;
-
-                    if let Some(llm_description) = rt.block_on(async {
-                        llm::get_llm_description(local_path, llm_client, llm_model).await
-                    }) {
-                        markdown.push_str("\n# Description:\n");
-                        markdown.push_str(&llm_description);
-                    }
-                }
-            }
-        }
-
-        println!("markdown:{}", markdown);
-
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content: markdown,
-        })
+        let bytes = fs::read(local_path)?;
+        let file_name = Path::new(local_path)
+            .file_name()
+            .and_then(|name| name.to_str())
+            .unwrap_or("");
+        image_to_markdown(&bytes, file_name, &args)
     }
 
     fn convert_bytes(
@@ -74,50 +52,861 @@ impl DocumentConverter for ImageConverter {
     ) -> Result<DocumentConverterResult, MarkitdownError> {
         if let Some(opts) = &args {
             if let Some(ext) = &opts.file_extension {
-                if ext != ".jpg" {
+                if !IMAGE_EXTENSIONS.contains(&ext.as_str()) {
                     return Err(MarkitdownError::InvalidFile(
-                        format!("Expected .jpg file, got {}", ext)
+                        format!("Expected an image file, got {}", ext)
                     ));
                 }
             }
         }
 
-        let exif = Reader::new().read_from_container(&mut Cursor::new(bytes))
-            .map_err(|e| MarkitdownError::ParseError(format!("Failed to read EXIF data: {}", e)))?;
+        // Bytes have no name of their own, but may have been fetched from a URL.
+        let url = args.as_ref().and_then(|opts| opts.url.as_deref());
+        let file_name = url
+            .and_then(|url| url.split(['?', '#']).next())
+            .and_then(|path| path.rsplit('/').next())
+            .unwrap_or("");
+        image_to_markdown(bytes, file_name, &args)
+    }
+}
+
+fn image_to_markdown(
+    bytes: &[u8],
+    file_name: &str,
+    args: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    let info = image_info(bytes)
+        .ok_or_else(|| MarkitdownError::InvalidFile("Unsupported image format".to_string()))?;
 
-        let mut markdown = String::new();
+    // Pictures without EXIF data, such as scans and screenshots, are common.
+    let exif = Reader::new()
+        .read_from_container(&mut Cursor::new(bytes))
+        .ok();
+    let xmp = xmp_packet(bytes).map(xmp_properties).unwrap_or_default();
+    let iptc = iptc_datasets(bytes);
 
-        for field in exif.fields() {
-            markdown.push_str(&format!(
-                "{}: {}\n",
-                field.tag,
-                field.display_value().with_unit(&exif)
-            ));
+    let mut markdown = String::new();
+    let mut metadata = BTreeMap::new();
+    metadata.insert("width".to_string(), info.width.to_string());
+    metadata.insert("height".to_string(), info.height.to_string());
+    for (label, value) in summary(&info, exif.as_ref(), &xmp, &iptc) {
+        markdown.push_str(&format!("- {}: {}\n", label, value));
+        if label != "Dimensions" {
+            metadata.insert(label.to_lowercase().replace(' ', "_"), value);
         }
+    }
+    // The curated summary, also offered to caption prompts as `{metadata}`.
+    let summary_text = markdown.clone();
 
-        if let Some(opts) = &args {
-            if let Some(llm_client) = &opts.llm_client {
-                if let Some(llm_model) = &opts.llm_model {
-                    let rt = tokio::runtime::Builder::new_current_thread()
-                        .enable_all()
-                        .build()
-                        .map_err(|e| MarkitdownError::Conversion(format!("Failed to create runtime: {}", e)))?;
-
-                    if let Some(llm_description) = rt.block_on(async {
-                        llm::get_llm_description("", llm_client, llm_model).await
-                    }) {
-                        markdown.push_str("\n# Description:\n");
-                        markdown.push_str(&llm_description);
-                    }
+    if args.as_ref().is_some_and(|opts| opts.image_full_metadata) {
+        if let Some(exif) = &exif {
+            markdown.push_str("\n## EXIF\n\n");
+            // TIFF files keep their XMP packet in tag 700, listed below by property.
+            for field in exif.fields().filter(|field| field.tag.number() != 700) {
+                markdown.push_str(&format!(
+                    "- {}: {}\n",
+                    field.tag,
+                    field.display_value().with_unit(exif)
+                ));
+            }
+        }
+        if !xmp.is_empty() {
+            markdown.push_str("\n## XMP\n\n");
+            for (name, value) in &xmp {
+                markdown.push_str(&format!("- {}: {}\n", name, value));
+            }
+        }
+        if !iptc.is_empty() {
+            markdown.push_str("\n## IPTC\n\n");
+            for (number, value) in &iptc {
+                markdown.push_str(&format!("- {}: {}\n", iptc_name(*number), value));
+            }
+        }
+    }
+
+    let caption_request = |opts: &ConversionOptions| {
+        CaptionRequest::from_options(
+            opts,
+            &[
+                ("file_name", file_name),
+                ("mime_type", info.mime_type()),
+                ("metadata", summary_text.trim_end()),
+            ],
+        )
+    };
+
+    let mut warnings = Vec::new();
+    if let Some((opts, llm)) = args
+        .as_ref()
+        .and_then(|opts| opts.llm.as_ref().map(|llm| (opts, llm)))
+    {
+        match llm.describe_image(bytes, info.mime_type(), &caption_request(opts)) {
+            Ok(llm_description) => {
+                markdown.push_str("\n# Description:\n");
+                markdown.push_str(&llm_description);
+            }
+            Err(e) => warnings.push(ConversionWarning {
+                line: None,
+                kind: WarningKind::Llm,
+                message: format!("Image captioning failed: {}", e),
+            }),
+        }
+    } else if let Some(opts) = args {
+        if let Some(llm_client) = &opts.llm_client {
+            if let Some(llm_model) = &opts.llm_model {
+                let rt = tokio::runtime::Builder::new_current_thread()
+                    .enable_all()
+                    .build()
+                    .map_err(|e| {
+                        MarkitdownError::Conversion(format!("Failed to create runtime: {}", e))
+                    })?;
+
+                let request = caption_request(opts);
+                if let Some(llm_description) = rt.block_on(async {
+                    llm::get_llm_description(
+                        bytes,
+                        info.mime_type(),
+                        llm_client,
+                        llm_model,
+                        &request,
+                    )
+                    .await
+                }) {
+                    markdown.push_str("\n# Description:\n");
+                    markdown.push_str(&llm_description);
                 }
             }
         }
+    }
 
-        println!("markdown:{}", markdown);
+    let mut ocr_lines = Vec::new();
+    if let Some(opts) = args.as_ref().filter(|opts| opts.ocr) {
+        ocr_lines = ocr_text(bytes, opts, &mut markdown, &mut warnings);
+    }
+
+    Ok(DocumentConverterResult {
+        title: None,
+        text_content: markdown,
+        warnings,
+        metadata,
+        ocr_lines,
+        ..Default::default()
+    })
+}
+
+/// Appends the text OCR finds in an image, reporting a failing engine as a
+/// warning.
+fn ocr_text(
+    image: &[u8],
+    opts: &ConversionOptions,
+    markdown: &mut String,
+    warnings: &mut Vec<ConversionWarning>,
+) -> Vec<OcrLine> {
+    let lines = match &opts.ocr_engine {
+        Some(engine) => engine.recognize(image),
+        None => TesseractOcr::default().recognize(image),
+    };
+    match lines {
+        Ok(lines) => {
+            if !lines.is_empty() {
+                markdown.push_str("\n# Text:\n");
+                for line in &lines {
+                    markdown.push_str(&line.text);
+                    markdown.push('\n');
+                }
+            }
+            lines
+        }
+        Err(e) => {
+            warnings.push(ConversionWarning {
+                line: None,
+                kind: WarningKind::Ocr,
+                message: format!("OCR failed: {}", e),
+            });
+            Vec::new()
+        }
+    }
+}
 
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content: markdown,
-        })
+/// The properties most readers look for, labelled for display. Text
+/// properties prefer XMP over EXIF over IPTC, as XMP is the most likely to
+/// be complete and in Unicode.
+fn summary(
+    info: &ImageInfo,
+    exif: Option<&Exif>,
+    xmp: &[(String, String)],
+    iptc: &[(u8, String)],
+) -> Vec<(&'static str, String)> {
+    let field = |tag: Tag| exif?.get_field(tag, In::PRIMARY);
+    let text = |tag: Tag| match &field(tag)?.value {
+        Value::Ascii(values) => {
+            let text = String::from_utf8_lossy(values.first()?);
+            let text = text.trim_matches(|c: char| c == '\0' || c.is_whitespace());
+            (!text.is_empty()).then(|| text.to_string())
+        }
+        _ => None,
+    };
+    let date = |tag: Tag| {
+        text(tag)?;
+        Some(field(tag)?.display_value().to_string())
+    };
+    let number = |tag: Tag| {
+        match &field(tag)?.value {
+            Value::Rational(values) => Some(values.first()?.to_f64()),
+            Value::SRational(values) => Some(values.first()?.to_f64()),
+            value => value.get_uint(0).map(f64::from),
+        }
+        .filter(|number| number.is_finite() && *number > 0.0)
+    };
+    let property = |name: &str| {
+        xmp.iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.clone())
+    };
+    let dataset = |number: u8| {
+        let values: Vec<&str> = iptc
+            .iter()
+            .filter(|(dataset, _)| *dataset == number)
+            .map(|(_, value)| value.as_str())
+            .collect();
+        (!values.is_empty()).then(|| values.join(", "))
+    };
+
+    let mut summary = vec![
+        ("Format", info.format.to_string()),
+        ("Dimensions", format!("{}x{}", info.width, info.height)),
+    ];
+    summary.extend(info.color.clone().map(|color| ("Color", color)));
+    if info.frames > 1 {
+        summary.push(("Frames", info.frames.to_string()));
+    }
+
+    let taken = date(Tag::DateTimeOriginal)
+        .or_else(|| date(Tag::DateTime))
+        .or_else(|| property("exif:DateTimeOriginal"))
+        .or_else(|| property("photoshop:DateCreated"))
+        .or_else(|| property("xmp:CreateDate"));
+    summary.extend(taken.map(|taken| ("Date taken", taken)));
+
+    let make = text(Tag::Make).or_else(|| property("tiff:Make"));
+    let model = text(Tag::Model).or_else(|| property("tiff:Model"));
+    let camera = match (make, model) {
+        // Models usually repeat the brand, as in `Canon` and `Canon EOS R5`.
+        (Some(make), Some(model)) => {
+            let brand = make.split_whitespace().next().unwrap_or_default();
+            if model.to_lowercase().starts_with(&brand.to_lowercase()) {
+                Some(model)
+            } else {
+                Some(format!("{} {}", make, model))
+            }
+        }
+        (make, model) => make.or(model),
+    };
+    summary.extend(camera.map(|camera| ("Camera", camera)));
+
+    let lens = text(Tag::LensModel)
+        .or_else(|| property("exifEX:LensModel"))
+        .or_else(|| property("aux:Lens"));
+    summary.extend(lens.map(|lens| ("Lens", lens)));
+
+    let mut exposure = Vec::new();
+    if let Some(time) = number(Tag::ExposureTime) {
+        exposure.push(match time {
+            time if time < 1.0 => format!("1/{} s", (1.0 / time).round()),
+            time => format!("{} s", decimal(time)),
+        });
+    }
+    exposure.extend(number(Tag::FNumber).map(|f| format!("f/{}", decimal(f))));
+    exposure.extend(number(Tag::PhotographicSensitivity).map(|iso| format!("ISO {}", iso)));
+    exposure.extend(number(Tag::FocalLength).map(|length| format!("{} mm", decimal(length))));
+    if !exposure.is_empty() {
+        summary.push(("Exposure", exposure.join(", ")));
+    }
+
+    // Degrees, minutes and seconds, negative to the south and west.
+    let coordinate = |tag: Tag, reference: Tag, negative: &str| {
+        let Value::Rational(parts) = &field(tag)?.value else {
+            return None;
+        };
+        let [degrees, minutes, seconds] = parts.get(..3)? else {
+            return None;
+        };
+        let value = degrees.to_f64() + minutes.to_f64() / 60.0 + seconds.to_f64() / 3600.0;
+        match text(reference).as_deref() == Some(negative) {
+            true => Some(-value),
+            false => Some(value),
+        }
+    };
+    let latitude = coordinate(Tag::GPSLatitude, Tag::GPSLatitudeRef, "S");
+    let longitude = coordinate(Tag::GPSLongitude, Tag::GPSLongitudeRef, "W");
+    if let (Some(latitude), Some(longitude)) = (latitude, longitude) {
+        summary.push(("GPS", format!("{:.6}, {:.6}", latitude, longitude)));
+        if let Some(altitude) = number(Tag::GPSAltitude) {
+            let below =
+                field(Tag::GPSAltitudeRef).and_then(|field| field.value.get_uint(0)) == Some(1);
+            let sign = if below { "-" } else { "" };
+            summary.push(("Altitude", format!("{}{} m", sign, decimal(altitude))));
+        }
+    }
+
+    let title = property("dc:title").or_else(|| dataset(5));
+    let description = property("dc:description")
+        .or_else(|| text(Tag::ImageDescription))
+        .or_else(|| dataset(120));
+    let keywords = property("dc:subject").or_else(|| dataset(25));
+    let artist = property("dc:creator")
+        .or_else(|| text(Tag::Artist))
+        .or_else(|| dataset(80));
+    let copyright = property("dc:rights")
+        .or_else(|| text(Tag::Copyright))
+        .or_else(|| dataset(116));
+    summary.extend(title.map(|title| ("Title", title)));
+    summary.extend(description.map(|description| ("Description", description)));
+    summary.extend(keywords.map(|keywords| ("Keywords", keywords)));
+    summary.extend(artist.map(|artist| ("Artist", artist)));
+    summary.extend(copyright.map(|copyright| ("Copyright", copyright)));
+    summary
+}
+
+/// Formats a number with at most two decimals and no trailing zeros.
+fn decimal(value: f64) -> String {
+    let text = format!("{:.2}", value);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+/// What the container says about the picture itself.
+struct ImageInfo {
+    format: &'static str,
+    width: u32,
+    height: u32,
+    /// Color model and depth, such as `RGBA, 8 bits per channel`.
+    color: Option<String>,
+    /// Number of frames, more than one for animations.
+    frames: u32,
+}
+
+impl ImageInfo {
+    fn mime_type(&self) -> &'static str {
+        match self.format {
+            "JPEG" => "image/jpeg",
+            "PNG" => "image/png",
+            "GIF" => "image/gif",
+            "WebP" => "image/webp",
+            "TIFF" => "image/tiff",
+            "HEIC" => "image/heic",
+            "AVIF" => "image/avif",
+            _ => "image/heif",
+        }
+    }
+}
+
+/// Reads the format, size and color model from the image header.
+fn image_info(bytes: &[u8]) -> Option<ImageInfo> {
+    if bytes.starts_with(b"\xff\xd8") {
+        jpeg_info(bytes)
+    } else if bytes.starts_with(b"\x89PNG\r\n\x1a\n") {
+        png_info(bytes)
+    } else if bytes.starts_with(b"GIF87a") || bytes.starts_with(b"GIF89a") {
+        gif_info(bytes)
+    } else if bytes.starts_with(b"RIFF") && bytes.get(8..12) == Some(b"WEBP") {
+        webp_info(bytes)
+    } else if bytes.starts_with(b"II*\0") || bytes.starts_with(b"MM\0*") {
+        tiff_info(bytes)
+    } else if bytes.get(4..8) == Some(b"ftyp") {
+        heif_info(bytes)
+    } else {
+        None
+    }
+}
+
+fn jpeg_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let mut offset = 2;
+    while offset + 4 <= bytes.len() {
+        if bytes[offset] != 0xff {
+            return None;
+        }
+        let marker = bytes[offset + 1];
+        // Fill bytes and markers that have no length.
+        if marker == 0xff {
+            offset += 1;
+            continue;
+        }
+        if marker == 0x01 || (0xd0..=0xd7).contains(&marker) {
+            offset += 2;
+            continue;
+        }
+        // Start of frame, other than DHT, JPG and DAC which share the range.
+        if (0xc0..=0xcf).contains(&marker) && !matches!(marker, 0xc4 | 0xc8 | 0xcc) {
+            let bits = *bytes.get(offset + 4)?;
+            let model = match bytes.get(offset + 9)? {
+                1 => "Grayscale",
+                3 => "RGB",
+                4 => "CMYK",
+                _ => return None,
+            };
+            return Some(ImageInfo {
+                format: "JPEG",
+                width: be16(bytes, offset + 7)?,
+                height: be16(bytes, offset + 5)?,
+                color: Some(format!("{}, {} bits per channel", model, bits)),
+                frames: 1,
+            });
+        }
+        // The entropy-coded data starts without a frame header.
+        if marker == 0xda {
+            return None;
+        }
+        offset += 2 + be16(bytes, offset + 2)? as usize;
+    }
+    None
+}
+
+fn png_info(bytes: &[u8]) -> Option<ImageInfo> {
+    if bytes.get(12..16)? != b"IHDR" {
+        return None;
+    }
+    let bits = *bytes.get(24)?;
+    let color = match bytes.get(25)? {
+        0 => format!("Grayscale, {} bits per channel", bits),
+        2 => format!("RGB, {} bits per channel", bits),
+        3 => format!("Indexed, {} bits per pixel", bits),
+        4 => format!("Grayscale with alpha, {} bits per channel", bits),
+        6 => format!("RGBA, {} bits per channel", bits),
+        _ => return None,
+    };
+
+    // Animated PNGs announce their frames in an acTL chunk before the data.
+    let mut frames = 1;
+    let mut offset = 8;
+    while let (Some(length), Some(kind)) = (be32(bytes, offset), bytes.get(offset + 4..offset + 8))
+    {
+        match kind {
+            b"acTL" => frames = be32(bytes, offset + 8)?,
+            b"IDAT" | b"IEND" => break,
+            _ => {}
+        }
+        offset += 12 + length as usize;
+    }
+
+    Some(ImageInfo {
+        format: "PNG",
+        width: be32(bytes, 16)?,
+        height: be32(bytes, 20)?,
+        color: Some(color),
+        frames,
+    })
+}
+
+fn gif_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let flags = *bytes.get(10)?;
+    let table = |flags: u8| match flags & 0x80 {
+        0 => 0,
+        _ => 3 << ((flags & 0x07) + 1),
+    };
+    let mut frames = 0;
+    let mut offset = 13 + table(flags);
+    // Counts image descriptors, stopping quietly at a truncated block.
+    loop {
+        match bytes.get(offset) {
+            Some(0x2c) => {
+                frames += 1;
+                let Some(&local) = bytes.get(offset + 9) else {
+                    break;
+                };
+                // Descriptor and local color table, then the LZW code size.
+                offset += 10 + table(local) + 1;
+            }
+            Some(0x21) => offset += 2,
+            _ => break,
+        }
+        let Some(end) = skip_sub_blocks(bytes, offset) else {
+            break;
+        };
+        offset = end;
+    }
+
+    Some(ImageInfo {
+        format: "GIF",
+        width: le16(bytes, 6)?,
+        height: le16(bytes, 8)?,
+        color: Some(format!("Indexed, {} bits per pixel", (flags & 0x07) + 1)),
+        frames: frames.max(1),
+    })
+}
+
+/// Returns the offset after a chain of GIF data sub-blocks.
+fn skip_sub_blocks(bytes: &[u8], mut offset: usize) -> Option<usize> {
+    loop {
+        let size = *bytes.get(offset)? as usize;
+        offset += 1 + size;
+        if size == 0 {
+            return Some(offset);
+        }
+    }
+}
+
+fn webp_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let mut size = None;
+    let mut alpha = false;
+    let mut frames = 0;
+    let mut offset = 12;
+    while let (Some(kind), Some(length)) = (bytes.get(offset..offset + 4), le32(bytes, offset + 4))
+    {
+        let data = offset + 8;
+        match kind {
+            // The extended header gives the canvas size for every kind of file.
+            b"VP8X" => {
+                alpha |= bytes.get(data)? & 0x10 != 0;
+                size = Some((le24(bytes, data + 4)? + 1, le24(bytes, data + 7)? + 1));
+            }
+            b"VP8 " if size.is_none() => {
+                if bytes.get(data + 3..data + 6)? != b"\x9d\x01\x2a" {
+                    return None;
+                }
+                size = Some((
+                    le16(bytes, data + 6)? & 0x3fff,
+                    le16(bytes, data + 8)? & 0x3fff,
+                ));
+            }
+            b"VP8L" if size.is_none() => {
+                let header = le32(bytes, data + 1)?;
+                size = Some(((header & 0x3fff) + 1, (header >> 14 & 0x3fff) + 1));
+                alpha |= header >> 28 & 1 != 0;
+            }
+            b"ALPH" => alpha = true,
+            b"ANMF" => frames += 1,
+            _ => {}
+        }
+        // Chunks are padded to an even length.
+        offset = data + length as usize + (length as usize & 1);
+    }
+
+    let (width, height) = size?;
+    Some(ImageInfo {
+        format: "WebP",
+        width,
+        height,
+        color: Some(format!(
+            "{}, 8 bits per channel",
+            if alpha { "RGBA" } else { "RGB" }
+        )),
+        frames: frames.max(1),
+    })
+}
+
+fn tiff_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let big_endian = bytes.starts_with(b"MM");
+    let u16_at = |offset| {
+        if big_endian {
+            be16(bytes, offset)
+        } else {
+            le16(bytes, offset)
+        }
+    };
+    let u32_at = |offset| {
+        if big_endian {
+            be32(bytes, offset)
+        } else {
+            le32(bytes, offset)
+        }
+    };
+
+    let ifd = u32_at(4)? as usize;
+    let mut tags = BTreeMap::new();
+    for index in 0..u16_at(ifd)? as usize {
+        let entry = ifd + 2 + index * 12;
+        let (tag, kind, count) = (u16_at(entry)?, u16_at(entry + 2)?, u32_at(entry + 4)?);
+        // The first value of SHORT and LONG entries, stored inline when it
+        // fits in four bytes.
+        let size: u32 = match kind {
+            3 => 2,
+            4 => 4,
+            _ => continue,
+        };
+        let Some(length) = size.checked_mul(count) else {
+            continue;
+        };
+        let at = match length {
+            0..=4 => entry + 8,
+            _ => u32_at(entry + 8)? as usize,
+        };
+        let value = if size == 2 { u16_at(at) } else { u32_at(at) };
+        tags.extend(value.map(|value| (tag, value)));
+    }
+
+    let samples = tags.get(&277).copied().unwrap_or(1);
+    let model = match (tags.get(&262)?, samples) {
+        (0 | 1, 1) => "Grayscale",
+        (0 | 1, _) => "Grayscale with alpha",
+        (2, 3) => "RGB",
+        (2, _) => "RGBA",
+        (3, _) => "Indexed",
+        (5, _) => "CMYK",
+        (6, _) => "YCbCr",
+        _ => return None,
+    };
+    let bits = tags.get(&258).copied().unwrap_or(1);
+    Some(ImageInfo {
+        format: "TIFF",
+        width: *tags.get(&256)?,
+        height: *tags.get(&257)?,
+        color: Some(format!("{}, {} bits per channel", model, bits)),
+        frames: 1,
+    })
+}
+
+fn heif_info(bytes: &[u8]) -> Option<ImageInfo> {
+    let format = match bytes.get(8..12)? {
+        b"heic" | b"heix" | b"heim" | b"heis" | b"hevc" | b"hevx" => "HEIC",
+        b"avif" | b"avis" => "AVIF",
+        _ => "HEIF",
+    };
+    let (_, meta) = boxes(bytes).find(|(kind, _)| *kind == b"meta")?;
+    // The meta box is a full box with a version and flags before its children.
+    let (_, properties) = boxes(meta.get(4..)?).find(|(kind, _)| *kind == b"iprp")?;
+    let (_, container) = boxes(properties).find(|(kind, _)| *kind == b"ipco")?;
+
+    // Thumbnails and grid tiles have their own sizes; the full picture is
+    // the largest.
+    let mut size: Option<(u32, u32)> = None;
+    let mut color = None;
+    for (kind, property) in boxes(container) {
+        match kind {
+            b"ispe" => {
+                let (width, height) = (be32(property, 4)?, be32(property, 8)?);
+                if size.is_none_or(|(w, h)| width as u64 * height as u64 > w as u64 * h as u64) {
+                    size = Some((width, height));
+                }
+            }
+            b"pixi" if color.is_none() => {
+                let channels = *property.get(4)?;
+                let bits = *property.get(5)?;
+                color = match channels {
+                    1 => Some(format!("Grayscale, {} bits per channel", bits)),
+                    3 => Some(format!("RGB, {} bits per channel", bits)),
+                    _ => None,
+                };
+            }
+            _ => {}
+        }
+    }
+
+    let (width, height) = size?;
+    Some(ImageInfo {
+        format,
+        width,
+        height,
+        color,
+        frames: 1,
+    })
+}
+
+/// Splits ISO base media data into boxes, yielding each type and body.
+fn boxes(bytes: &[u8]) -> impl Iterator<Item = (&[u8], &[u8])> {
+    let mut offset = 0;
+    std::iter::from_fn(move || {
+        let kind = bytes.get(offset + 4..offset + 8)?;
+        let (header, size) = match be32(bytes, offset)? {
+            0 => (8, bytes.len() - offset),
+            1 => (
+                16,
+                usize::try_from(u64::from_be_bytes(
+                    bytes.get(offset + 8..offset + 16)?.try_into().ok()?,
+                ))
+                .ok()?,
+            ),
+            size => (8, size as usize),
+        };
+        let body = bytes.get(offset + header..offset.checked_add(size)?)?;
+        offset += size;
+        Some((kind, body))
+    })
+}
+
+fn be16(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u16::from_be_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?) as u32)
+}
+
+fn be32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_be_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn le16(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u16::from_le_bytes(bytes.get(offset..offset + 2)?.try_into().ok()?) as u32)
+}
+
+fn le24(bytes: &[u8], offset: usize) -> Option<u32> {
+    let bytes = bytes.get(offset..offset + 3)?;
+    Some(u32::from_le_bytes([bytes[0], bytes[1], bytes[2], 0]))
+}
+
+fn le32(bytes: &[u8], offset: usize) -> Option<u32> {
+    Some(u32::from_le_bytes(
+        bytes.get(offset..offset + 4)?.try_into().ok()?,
+    ))
+}
+
+fn find(haystack: &[u8], needle: &[u8]) -> Option<usize> {
+    haystack
+        .windows(needle.len())
+        .position(|window| window == needle)
+}
+
+/// Finds the XMP packet. Every container stores it as plain XML, so it is
+/// found without walking the container; compressed PNG text chunks are
+/// missed.
+fn xmp_packet(bytes: &[u8]) -> Option<&str> {
+    let (open, close): (&[u8], &[u8]) = match find(bytes, b"<x:xmpmeta") {
+        Some(_) => (b"<x:xmpmeta", b"</x:xmpmeta>"),
+        None => (b"<rdf:RDF", b"</rdf:RDF>"),
+    };
+    let start = find(bytes, open)?;
+    let end = start + find(&bytes[start..], close)? + close.len();
+    std::str::from_utf8(&bytes[start..end]).ok()
+}
+
+/// Reads the IPTC application record from a Photoshop image resource, as
+/// stored in JPEG APP13 segments and TIFF tag 34377. Each dataset is listed
+/// by number, repeated datasets such as keywords once per value.
+fn iptc_datasets(bytes: &[u8]) -> Vec<(u8, String)> {
+    let mut datasets = Vec::new();
+    let Some(start) = find(bytes, b"8BIM\x04\x04") else {
+        return datasets;
+    };
+    // The resource name is a Pascal string padded to an even length.
+    let Some(&name) = bytes.get(start + 6) else {
+        return datasets;
+    };
+    let offset = start + 6 + ((name as usize + 2) & !1);
+    let Some(data) =
+        be32(bytes, offset).and_then(|size| bytes.get(offset + 4..offset + 4 + size as usize))
+    else {
+        return datasets;
+    };
+
+    let mut offset = 0;
+    while data.get(offset) == Some(&0x1c) {
+        let (Some(&record), Some(&number), Some(length)) = (
+            data.get(offset + 1),
+            data.get(offset + 2),
+            be16(data, offset + 3),
+        ) else {
+            break;
+        };
+        // Extended lengths are only used for binary data.
+        if length & 0x8000 != 0 {
+            break;
+        }
+        let Some(value) = data.get(offset + 5..offset + 5 + length as usize) else {
+            break;
+        };
+        if record == 2 && number != 0 {
+            // Older files use Latin-1 rather than UTF-8.
+            let value = String::from_utf8(value.to_vec())
+                .unwrap_or_else(|_| value.iter().map(|&byte| byte as char).collect());
+            datasets.push((number, value.trim().to_string()));
+        }
+        offset += 5 + length as usize;
+    }
+    datasets
+}
+
+fn iptc_name(number: u8) -> String {
+    match number {
+        5 => "Object Name",
+        25 => "Keywords",
+        55 => "Date Created",
+        80 => "By-line",
+        90 => "City",
+        95 => "Province/State",
+        101 => "Country",
+        105 => "Headline",
+        110 => "Credit",
+        115 => "Source",
+        116 => "Copyright Notice",
+        120 => "Caption/Abstract",
+        _ => return format!("Dataset 2:{}", number),
+    }
+    .to_string()
+}
+
+/// Lists the simple XMP properties, such as `dc:title` or
+/// `xmp:CreatorTool`, with the items of arrays joined by `, `. Structured
+/// values like edit histories are left out.
+fn xmp_properties(packet: &str) -> Vec<(String, String)> {
+    let mut properties = Vec::new();
+    // The property element being read, the values found in it, whether it
+    // holds a structure, and how deep its array elements are nested.
+    let mut property: Option<(String, Vec<String>, bool)> = None;
+    let mut depth = 0;
+    let mut in_description = false;
+    let mut reader = quick_xml::Reader::from_str(packet);
+
+    // Properties can also be written as attributes of rdf:Description.
+    let attributes =
+        |element: &BytesStart, decoder: Decoder, properties: &mut Vec<(String, String)>| {
+            for attribute in element.attributes().flatten() {
+                let name = String::from_utf8_lossy(attribute.key.as_ref()).into_owned();
+                if name.starts_with("xmlns") || name.starts_with("rdf:") {
+                    continue;
+                }
+                if let Ok(value) = attribute.decode_and_unescape_value(decoder) {
+                    if !value.trim().is_empty() {
+                        properties.push((name, value.trim().to_string()));
+                    }
+                }
+            }
+        };
+
+    loop {
+        match reader.read_event() {
+            Ok(Event::Start(element)) => {
+                let name = String::from_utf8_lossy(element.name().as_ref()).into_owned();
+                if let Some((_, _, structured)) = &mut property {
+                    depth += 1;
+                    if !matches!(name.as_str(), "rdf:Bag" | "rdf:Seq" | "rdf:Alt" | "rdf:li") {
+                        *structured = true;
+                    }
+                } else if name == "rdf:Description" {
+                    in_description = true;
+                    attributes(&element, reader.decoder(), &mut properties);
+                } else if in_description {
+                    property = Some((name, Vec::new(), false));
+                    depth = 0;
+                }
+            }
+            Ok(Event::Empty(element)) => {
+                if let Some((_, _, structured)) = &mut property {
+                    *structured = true;
+                } else if element.name().as_ref() == b"rdf:Description" {
+                    attributes(&element, reader.decoder(), &mut properties);
+                }
+            }
+            Ok(Event::Text(text)) => {
+                if let (Some((_, values, _)), Ok(text)) = (&mut property, text.unescape()) {
+                    if !text.trim().is_empty() {
+                        values.push(text.trim().to_string());
+                    }
+                }
+            }
+            Ok(Event::End(element)) => {
+                if property.is_some() {
+                    if depth > 0 {
+                        depth -= 1;
+                    } else if let Some((name, values, structured)) = property.take() {
+                        if !structured && !values.is_empty() {
+                            properties.push((name, values.join(", ")));
+                        }
+                    }
+                } else if element.name().as_ref() == b"rdf:Description" {
+                    in_description = false;
+                }
+            }
+            Ok(Event::Eof) | Err(_) => break,
+            _ => {}
+        }
     }
+    properties
 }
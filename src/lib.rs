@@ -2,13 +2,20 @@ pub mod csv;
 pub mod docx;
 pub mod error;
 pub mod excel;
+pub mod github;
 pub mod html;
 pub mod image;
 pub mod llm;
+pub mod mhtml;
 pub mod model;
+pub mod ocr;
 pub mod pdf;
+mod pdf_encryption;
 pub mod pptx;
 pub mod rss;
+pub mod stackexchange;
+mod table;
+pub mod wikipedia;
 
 use csv::CsvConverter;
 use docx::DocxConverter;
@@ -17,6 +24,7 @@ use excel::ExcelConverter;
 use html::HtmlConverter;
 use image::ImageConverter;
 use infer;
+use mhtml::MhtmlConverter;
 use mime_guess::MimeGuess;
 use model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
 use pdf::PdfConverter;
@@ -42,6 +50,7 @@ impl MarkItDown {
         md.register_converter(Box::new(CsvConverter));
         md.register_converter(Box::new(ExcelConverter));
         md.register_converter(Box::new(HtmlConverter));
+        md.register_converter(Box::new(MhtmlConverter));
         md.register_converter(Box::new(ImageConverter));
         md.register_converter(Box::new(RssConverter));
         md.register_converter(Box::new(PdfConverter));
@@ -68,10 +77,29 @@ impl MarkItDown {
             "application/vnd.openxmlformats-officedocument.spreadsheetml.sheet",
             vec![".xlsx"],
         );
+        map.insert(
+            "application/vnd.ms-excel.sheet.macroEnabled.12",
+            vec![".xlsm"],
+        );
+        map.insert(
+            "application/vnd.ms-excel.sheet.binary.macroEnabled.12",
+            vec![".xlsb"],
+        );
+        map.insert(
+            "application/vnd.oasis.opendocument.spreadsheet",
+            vec![".ods"],
+        );
+        map.insert("text/csv", vec![".csv"]);
+        map.insert("text/tab-separated-values", vec![".tsv", ".tab"]);
         map.insert("text/html", vec![".html", ".htm"]);
+        map.insert("multipart/related", vec![".mhtml", ".mht"]);
         map.insert("image/jpeg", vec![".jpg", ".jpeg"]);
         map.insert("image/png", vec![".png"]);
         map.insert("image/gif", vec![".gif"]);
+        map.insert("image/webp", vec![".webp"]);
+        map.insert("image/tiff", vec![".tiff", ".tif"]);
+        map.insert("image/heic", vec![".heic"]);
+        map.insert("image/heif", vec![".heif"]);
         map.insert("application/zip", vec![".zip"]);
         map.insert("audio/mpeg", vec![".mp3"]);
         map.insert("audio/wav", vec![".wav"]);
@@ -122,9 +150,7 @@ impl MarkItDown {
         } else {
             args = Some(ConversionOptions {
                 file_extension: self.detect_file_type(source),
-                url: None,
-                llm_client: None,
-                llm_model: None,
+                ..Default::default()
             });
         }
 
@@ -163,9 +189,7 @@ impl MarkItDown {
                                         )
                                     })?,
                                 ),
-                                url: None,
-                                llm_client: None,
-                                llm_model: None,
+                                ..Default::default()
                             });
                             match converter.convert(
                                 file_path.to_str().ok_or_else(|| {
@@ -191,6 +215,7 @@ impl MarkItDown {
                     return Ok(Some(DocumentConverterResult {
                         title: None,
                         text_content: markdown,
+                        ..Default::default()
                     }));
                 }
             }
@@ -199,6 +224,14 @@ impl MarkItDown {
         for converter in &self.converters {
             match converter.convert(source, args.clone()) {
                 Ok(result) => return Ok(Some(result)),
+                // The converter recognised the file, so the caller has to act.
+                Err(
+                    error @ (MarkitdownError::PasswordRequired(_)
+                    | MarkitdownError::WrongPassword(_)
+                    | MarkitdownError::UnsupportedEncryption(_)
+                    | MarkitdownError::Malformed(_)
+                    | MarkitdownError::InvalidOption(_)),
+                ) => return Err(error),
                 Err(_) => continue, // Try next converter
             }
         }
@@ -217,9 +250,7 @@ impl MarkItDown {
         } else {
             args = Some(ConversionOptions {
                 file_extension: self.detect_bytes(bytes),
-                url: None,
-                llm_client: None,
-                llm_model: None,
+                ..Default::default()
             });
         }
 
@@ -242,9 +273,7 @@ impl MarkItDown {
                         for converter in &self.converters {
                             let file_args = Some(ConversionOptions {
                                 file_extension: self.detect_file_type(&file_name),
-                                url: None,
-                                llm_client: None,
-                                llm_model: None,
+                                ..Default::default()
                             });
                             match converter.convert_bytes(&file_contents, file_args.clone()) {
                                 Ok(result) => {
@@ -261,6 +290,7 @@ impl MarkItDown {
                     return Ok(Some(DocumentConverterResult {
                         title: None,
                         text_content: markdown,
+                        ..Default::default()
                     }));
                 }
             }
@@ -269,6 +299,13 @@ impl MarkItDown {
         for converter in &self.converters {
             match converter.convert_bytes(bytes, args.clone()) {
                 Ok(result) => return Ok(Some(result)),
+                Err(
+                    error @ (MarkitdownError::PasswordRequired(_)
+                    | MarkitdownError::WrongPassword(_)
+                    | MarkitdownError::UnsupportedEncryption(_)
+                    | MarkitdownError::Malformed(_)
+                    | MarkitdownError::InvalidOption(_)),
+                ) => return Err(error),
                 Err(_) => continue, // Try next converter
             }
         }
@@ -1,8 +1,10 @@
+use std::fmt::Debug;
+
 use base64::prelude::*;
 use rig::{
     agent::Agent,
     completion::{CompletionModel, Prompt},
-    message::{ContentFormat, ImageMediaType, Message, UserContent},
+    message::{ContentFormat, ImageMediaType, Message, MimeType, UserContent},
     providers::{
         deepseek,
         gemini::{self, completion::gemini_api_types::GenerationConfig},
@@ -10,40 +12,261 @@ use rig::{
     },
     OneOrMany,
 };
+use serde_json::json;
+
+use crate::error::MarkitdownError;
+use crate::model::ConversionOptions;
+
+const PREAMBLE: &str = "You are an image describer.";
+const PROMPT: &str = "Write a detailed caption for this image.";
+const TEMPERATURE: f64 = 0.5;
+
+/// What to ask a model about an image and how it should sample its answer.
+#[derive(Debug, Clone, PartialEq)]
+pub struct CaptionRequest {
+    /// System message setting up the model's role.
+    pub preamble: String,
+    /// Question sent with the image.
+    pub prompt: String,
+    pub temperature: f64,
+    /// Upper bound on the length of the answer in tokens.
+    pub max_tokens: Option<u64>,
+    /// Provider-specific fields merged into the request body, such as
+    /// `{"top_p": 0.9}`. Gemini reads them as a `GenerationConfig`.
+    pub params: Option<serde_json::Value>,
+}
+
+impl Default for CaptionRequest {
+    fn default() -> Self {
+        CaptionRequest {
+            preamble: PREAMBLE.to_string(),
+            prompt: PROMPT.to_string(),
+            temperature: TEMPERATURE,
+            max_tokens: None,
+            params: None,
+        }
+    }
+}
+
+impl CaptionRequest {
+    /// Builds the request from the `llm_*` options, filling the prompt
+    /// template's `{name}` placeholders from `variables`. `{context}` is
+    /// always taken from `llm_context`.
+    pub fn from_options(options: &ConversionOptions, variables: &[(&str, &str)]) -> Self {
+        let context = options.llm_context.as_deref().unwrap_or("");
+        let mut variables = variables.to_vec();
+        variables.push(("context", context));
+
+        let defaults = CaptionRequest::default();
+        CaptionRequest {
+            preamble: options.llm_preamble.clone().unwrap_or(defaults.preamble),
+            prompt: options
+                .llm_prompt
+                .as_deref()
+                .map(|template| render_template(template, &variables))
+                .unwrap_or(defaults.prompt),
+            temperature: options.llm_temperature.unwrap_or(defaults.temperature),
+            max_tokens: options.llm_max_tokens,
+            params: options.llm_params.clone(),
+        }
+    }
+
+    /// Parameters for OpenAI-style chat completions, which take the token
+    /// limit as `max_tokens` next to any provider-specific fields.
+    fn chat_params(&self) -> Option<serde_json::Value> {
+        let mut params = match &self.params {
+            Some(serde_json::Value::Object(params)) => params.clone(),
+            _ => serde_json::Map::new(),
+        };
+        if let Some(max_tokens) = self.max_tokens {
+            params
+                .entry("max_tokens")
+                .or_insert_with(|| json!(max_tokens));
+        }
+        (!params.is_empty()).then_some(serde_json::Value::Object(params))
+    }
+}
+
+/// Replaces `{name}` placeholders with their values, leaving unknown names
+/// and stray braces as they are.
+fn render_template(template: &str, variables: &[(&str, &str)]) -> String {
+    let mut rendered = String::with_capacity(template.len());
+    let mut rest = template;
+    while let Some(start) = rest.find('{') {
+        rendered.push_str(&rest[..start]);
+        rest = &rest[start..];
+        let value = rest.find('}').and_then(|end| {
+            let name = &rest[1..end];
+            variables
+                .iter()
+                .find(|(variable, _)| *variable == name)
+                .map(|(_, value)| (*value, end))
+        });
+        match value {
+            Some((value, end)) => {
+                rendered.push_str(value);
+                rest = &rest[end + 1..];
+            }
+            None => {
+                rendered.push('{');
+                rest = &rest[1..];
+            }
+        }
+    }
+    rendered.push_str(rest);
+    rendered
+}
+
+/// A language model that captions images.
+pub trait LlmClient: Debug + Send + Sync {
+    /// Answers `request` about an image given as its encoded bytes and MIME
+    /// type, such as `image/png`.
+    fn describe_image(
+        &self,
+        image: &[u8],
+        mime_type: &str,
+        request: &CaptionRequest,
+    ) -> Result<String, MarkitdownError>;
+}
+
+/// A chat completions API compatible with OpenAI's, as served by OpenAI,
+/// Ollama, vLLM and others.
+#[derive(Debug, Clone)]
+pub struct OpenAiCompatibleClient {
+    /// Base URL of the API, such as `https://api.openai.com/v1` or
+    /// `http://localhost:11434/v1` for a local Ollama.
+    pub base_url: String,
+    pub model: String,
+    /// Sent as a bearer token when set.
+    pub api_key: Option<String>,
+    /// Extra headers sent with every request.
+    pub headers: Vec<(String, String)>,
+}
+
+impl OpenAiCompatibleClient {
+    pub fn new(base_url: &str, model: &str) -> Self {
+        OpenAiCompatibleClient {
+            base_url: base_url.to_string(),
+            model: model.to_string(),
+            api_key: None,
+            headers: Vec::new(),
+        }
+    }
+}
+
+impl LlmClient for OpenAiCompatibleClient {
+    fn describe_image(
+        &self,
+        image: &[u8],
+        mime_type: &str,
+        request: &CaptionRequest,
+    ) -> Result<String, MarkitdownError> {
+        let url = format!("{}/chat/completions", self.base_url.trim_end_matches('/'));
+        let mut body = json!({
+            "model": self.model,
+            "temperature": request.temperature,
+            "messages": [
+                { "role": "system", "content": request.preamble },
+                {
+                    "role": "user",
+                    "content": [
+                        { "type": "text", "text": request.prompt },
+                        {
+                            "type": "image_url",
+                            "image_url": {
+                                "url": format!(
+                                    "data:{};base64,{}",
+                                    mime_type,
+                                    BASE64_STANDARD.encode(image)
+                                )
+                            }
+                        }
+                    ]
+                }
+            ]
+        });
+        if let (Some(serde_json::Value::Object(params)), Some(body)) =
+            (request.chat_params(), body.as_object_mut())
+        {
+            body.extend(params);
+        }
+
+        let mut request = reqwest::blocking::Client::new().post(&url).json(&body);
+        if let Some(api_key) = &self.api_key {
+            request = request.bearer_auth(api_key);
+        }
+        for (name, value) in &self.headers {
+            request = request.header(name, value);
+        }
+        let response = request.send().map_err(|e| {
+            MarkitdownError::NetworkError(format!("Request to {} failed: {}", url, e))
+        })?;
+        let status = response.status();
+        let text = response.text().map_err(|e| {
+            MarkitdownError::NetworkError(format!("Failed to read response: {}", e))
+        })?;
+        if !status.is_success() {
+            return Err(MarkitdownError::NetworkError(format!(
+                "{} returned {}: {}",
+                url,
+                status,
+                text.trim()
+            )));
+        }
 
+        let response: serde_json::Value = serde_json::from_str(&text).map_err(|e| {
+            MarkitdownError::ParseError(format!("Invalid completion response: {}", e))
+        })?;
+        response["choices"][0]["message"]["content"]
+            .as_str()
+            .map(|content| content.trim().to_string())
+            .ok_or_else(|| {
+                MarkitdownError::ParseError(
+                    "Completion response has no message content".to_string(),
+                )
+            })
+    }
+}
+
+/// Asks the model about an image given as its encoded bytes and MIME type,
+/// such as `image/png`. Returns `None` for media types the providers don't
+/// accept, such as TIFF.
 pub async fn get_llm_description(
-    local_path: &str,
+    image: &[u8],
+    mime_type: &str,
     llm_client: &str,
     llm_model: &str,
+    request: &CaptionRequest,
 ) -> Option<String> {
+    let media_type = ImageMediaType::from_mime_type(mime_type)?;
+    let data = BASE64_STANDARD.encode(image);
+    // OpenAI-compatible APIs take the image as a data URL, Gemini as plain
+    // base64 next to its media type.
+    let data = match llm_client {
+        "gemini" => data,
+        _ => format!("data:{};base64,{}", mime_type, data),
+    };
+    let image = UserContent::image(data, Some(ContentFormat::Base64), Some(media_type), None);
+
     match llm_client {
-        "gemini" => get_llm_description_by_gemini(local_path, llm_model).await,
-        "openai" => get_llm_description_by_openai(local_path, llm_model).await,
-        "deepseek" => get_llm_description_by_deepseek(local_path, llm_model).await,
+        "gemini" => get_llm_description_by_gemini(image, llm_model, request).await,
+        "openai" => get_llm_description_by_openai(image, llm_model, request).await,
+        "deepseek" => get_llm_description_by_deepseek(image, llm_model, request).await,
         _ => {
             println!("Unsupported llm_client: {}", llm_client);
-            return None;
+            None
         }
     }
 }
 
-async fn get_answer(agent: Agent<impl CompletionModel>, local_path: &str) -> Option<String> {
-    let image = match std::fs::read(local_path) {
-        Ok(i) => i,
-        Err(_) => return None,
-    };
-    let image_base64 = BASE64_STANDARD.encode(image);
-
-    let mut content_items = OneOrMany::one(UserContent::image(
-        image_base64,
-        Some(ContentFormat::Base64),
-        Some(ImageMediaType::JPEG),
-        None,
-    ));
+async fn get_answer(
+    agent: Agent<impl CompletionModel>,
+    image: UserContent,
+    prompt: &str,
+) -> Option<String> {
+    let mut content_items = OneOrMany::one(image);
 
-    content_items.push(UserContent::text(
-        "Write a detailed caption for this image.",
-    ));
+    content_items.push(UserContent::text(prompt));
 
     let message = Message::User {
         content: content_items,
@@ -63,65 +286,68 @@ async fn get_answer(agent: Agent<impl CompletionModel>, local_path: &str) -> Opt
     }
 }
 
-async fn get_llm_description_by_gemini(local_path: &str, llm_model: &str) -> Option<String> {
-    let params = serde_json::to_value(GenerationConfig {
-        top_k: Some(1),
-        top_p: Some(0.95),
-        candidate_count: Some(1),
-        ..Default::default()
-    })
-    .unwrap();
+async fn get_llm_description_by_gemini(
+    image: UserContent,
+    llm_model: &str,
+    request: &CaptionRequest,
+) -> Option<String> {
+    let params = match &request.params {
+        Some(params) => params.clone(),
+        None => serde_json::to_value(GenerationConfig {
+            top_k: Some(1),
+            top_p: Some(0.95),
+            candidate_count: Some(1),
+            ..Default::default()
+        })
+        .unwrap(),
+    };
 
     let client = gemini::Client::from_env();
 
-    let agent = client
+    let mut agent = client
         .agent(llm_model)
-        .preamble("You are an image describer.")
-        .temperature(0.5)
-        .additional_params(params)
-        .build();
+        .preamble(&request.preamble)
+        .temperature(request.temperature)
+        .additional_params(params);
+    if let Some(max_tokens) = request.max_tokens {
+        agent = agent.max_tokens(max_tokens);
+    }
 
-    get_answer(agent, local_path).await
+    get_answer(agent.build(), image, &request.prompt).await
 }
 
-async fn get_llm_description_by_openai(local_path: &str, llm_model: &str) -> Option<String> {
-    let params = serde_json::to_value(GenerationConfig {
-        top_k: Some(1),
-        top_p: Some(0.95),
-        candidate_count: Some(1),
-        ..Default::default()
-    })
-    .unwrap();
-
+async fn get_llm_description_by_openai(
+    image: UserContent,
+    llm_model: &str,
+    request: &CaptionRequest,
+) -> Option<String> {
     let client = openai::Client::from_env();
 
-    let agent = client
+    let mut agent = client
         .agent(llm_model)
-        .preamble("You are an image describer.")
-        .temperature(0.5)
-        .additional_params(params)
-        .build();
+        .preamble(&request.preamble)
+        .temperature(request.temperature);
+    if let Some(params) = request.chat_params() {
+        agent = agent.additional_params(params);
+    }
 
-    get_answer(agent, local_path).await
+    get_answer(agent.build(), image, &request.prompt).await
 }
 
-async fn get_llm_description_by_deepseek(local_path: &str, llm_model: &str) -> Option<String> {
-    let params = serde_json::to_value(GenerationConfig {
-        top_k: Some(1),
-        top_p: Some(0.95),
-        candidate_count: Some(1),
-        ..Default::default()
-    })
-    .unwrap();
-
+async fn get_llm_description_by_deepseek(
+    image: UserContent,
+    llm_model: &str,
+    request: &CaptionRequest,
+) -> Option<String> {
     let client = deepseek::Client::from_env();
 
-    let agent = client
+    let mut agent = client
         .agent(llm_model)
-        .preamble("You are an image describer.")
-        .temperature(0.5)
-        .additional_params(params)
-        .build();
+        .preamble(&request.preamble)
+        .temperature(request.temperature);
+    if let Some(params) = request.chat_params() {
+        agent = agent.additional_params(params);
+    }
 
-    get_answer(agent, local_path).await
+    get_answer(agent.build(), image, &request.prompt).await
 }
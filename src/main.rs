@@ -14,6 +14,15 @@ struct Cli {
 
     #[arg(short, long)]
     format: Option<String>,
+
+    #[arg(short, long)]
+    password: Option<String>,
+
+    #[arg(long)]
+    ocr: bool,
+
+    #[arg(long)]
+    full_metadata: bool,
 }
 
 fn main() -> Result<(), Box<dyn std::error::Error>> {
@@ -55,9 +64,10 @@ fn main() -> Result<(), Box<dyn std::error::Error>> {
             } else {
                 Some(format!(".{}", format))
             },
-            url: None,
-            llm_client: None,
-            llm_model: None,
+            password: cli.password,
+            ocr: cli.ocr,
+            image_full_metadata: cli.full_metadata,
+            ..Default::default()
         }),
     )?;
 
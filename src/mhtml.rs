@@ -0,0 +1,424 @@
+use std::collections::HashMap;
+use std::rc::Rc;
+
+use base64::Engine;
+use encoding_rs::Encoding;
+use html5ever::tendril::StrTendril;
+use markup5ever_rcdom::{Handle, NodeData};
+
+use crate::error::MarkitdownError;
+use crate::html::{
+    attr, element_name, find_element, parse_html, serialize_node, walk, HtmlConverter,
+};
+use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+
+/// Converts web archives saved by browsers as a single file (`.mht`,
+/// `.mhtml`). The root HTML part is converted by `HtmlConverter`, which
+/// applies the rules of a site when it recognises the page.
+pub struct MhtmlConverter;
+
+const MHTML_EXTENSIONS: [&str; 2] = [".mht", ".mhtml"];
+
+/// Attributes whose `cid:` URLs are rewritten to the referenced part's
+/// original location.
+const URL_ATTRIBUTES: [&str; 4] = ["src", "href", "poster", "data"];
+
+/// How deeply frames saved as separate parts are inlined.
+const MAX_FRAME_DEPTH: usize = 4;
+
+impl DocumentConverter for MhtmlConverter {
+    fn convert(
+        &self,
+        local_path: &str,
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        if let Some(opts) = &args {
+            if let Some(ext) = &opts.file_extension {
+                if !MHTML_EXTENSIONS.contains(&ext.as_str()) {
+                    return Err(MarkitdownError::InvalidFile(format!(
+                        "Expected .mht or .mhtml file, got {}",
+                        ext
+                    )));
+                }
+            }
+        }
+
+        let bytes = std::fs::read(local_path)?;
+        mhtml_to_markdown(&bytes, &args)
+    }
+
+    fn convert_bytes(
+        &self,
+        bytes: &[u8],
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        if let Some(opts) = &args {
+            if let Some(ext) = &opts.file_extension {
+                if !MHTML_EXTENSIONS.contains(&ext.as_str()) {
+                    return Err(MarkitdownError::InvalidFile(format!(
+                        "Expected .mht or .mhtml file, got {}",
+                        ext
+                    )));
+                }
+            }
+        }
+
+        mhtml_to_markdown(bytes, &args)
+    }
+}
+
+fn mhtml_to_markdown(
+    bytes: &[u8],
+    args: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    let message = Part::parse(bytes);
+    if message.header("mime-version").is_none() && message.header("content-type").is_none() {
+        return Err(MarkitdownError::InvalidFile(
+            "Not a MIME web archive".to_string(),
+        ));
+    }
+
+    let mut parts = Vec::new();
+    message.flatten_into(&mut parts);
+    let root = root_part(&message, &parts)
+        .ok_or_else(|| MarkitdownError::InvalidFile("Web archive has no HTML part".to_string()))?;
+
+    let archive = Archive::new(&parts);
+    let document = parse_html(&root.text()?);
+    archive.resolve_references(&document, 0)?;
+
+    let mut options = args.clone().unwrap_or_default();
+    options.file_extension = Some(".html".to_string());
+    if options.url.is_none() {
+        options.url = root
+            .header("content-location")
+            .or_else(|| message.header("snapshot-content-location"))
+            .map(str::to_string);
+    }
+    let html = serialize_node(&document);
+    // `HtmlConverter` tries the site-specific conversions first, so saved
+    // pages get the same handling as HTML files.
+    let mut result = HtmlConverter.convert_bytes(html.as_bytes(), Some(options))?;
+    if result.title.is_none() {
+        result.title = message.header("subject").map(decode_encoded_words);
+    }
+    Ok(result)
+}
+
+/// The part named by the `start` parameter of a `multipart/related`
+/// message, otherwise the first HTML part.
+fn root_part<'a>(message: &'a Part, parts: &'a [Part]) -> Option<&'a Part> {
+    if let Some(start) = message.parameter("start") {
+        let start = start.trim_matches(|c| c == '<' || c == '>');
+        if let Some(part) = parts.iter().find(|part| part.content_id() == Some(start)) {
+            return Some(part);
+        }
+    }
+    parts.iter().find(|part| part.mime_type() == "text/html")
+}
+
+/// A MIME entity: its headers and its still-encoded body.
+#[derive(Clone)]
+struct Part {
+    headers: Vec<(String, String)>,
+    body: Vec<u8>,
+}
+
+impl Part {
+    fn parse(bytes: &[u8]) -> Part {
+        let mut headers: Vec<(String, String)> = Vec::new();
+        let mut offset = 0;
+        for line in bytes.split_inclusive(|&b| b == b'\n') {
+            offset += line.len();
+            let line = String::from_utf8_lossy(trim_line_end(line));
+            if line.is_empty() {
+                break;
+            }
+            // Folded header lines continue the previous header.
+            if line.starts_with([' ', '\t']) {
+                if let Some((_, value)) = headers.last_mut() {
+                    value.push(' ');
+                    value.push_str(line.trim());
+                }
+                continue;
+            }
+            if let Some((name, value)) = line.split_once(':') {
+                headers.push((name.trim().to_lowercase(), value.trim().to_string()));
+            }
+        }
+        Part {
+            headers,
+            body: bytes[offset.min(bytes.len())..].to_vec(),
+        }
+    }
+
+    fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header == name)
+            .map(|(_, value)| value.as_str())
+    }
+
+    fn mime_type(&self) -> String {
+        self.header("content-type")
+            .and_then(|value| value.split(';').next())
+            .map(|mime| mime.trim().to_lowercase())
+            .unwrap_or_else(|| "text/plain".to_string())
+    }
+
+    /// A parameter of the `Content-Type` header, such as `boundary`.
+    fn parameter(&self, name: &str) -> Option<String> {
+        let content_type = self.header("content-type")?;
+        content_type.split(';').skip(1).find_map(|parameter| {
+            let (key, value) = parameter.split_once('=')?;
+            key.trim()
+                .eq_ignore_ascii_case(name)
+                .then(|| value.trim().trim_matches('"').to_string())
+        })
+    }
+
+    fn content_id(&self) -> Option<&str> {
+        self.header("content-id")
+            .map(|id| id.trim_matches(|c| c == '<' || c == '>'))
+    }
+
+    /// Collects the leaf parts of nested multipart bodies in order.
+    fn flatten_into(&self, parts: &mut Vec<Part>) {
+        let boundary = self.parameter("boundary");
+        match boundary {
+            Some(boundary) if self.mime_type().starts_with("multipart/") => {
+                for body in split_multipart(&self.body, &boundary) {
+                    Part::parse(body).flatten_into(parts);
+                }
+            }
+            _ => parts.push(self.clone()),
+        }
+    }
+
+    /// The body with its transfer encoding removed.
+    fn decoded_body(&self) -> Result<Vec<u8>, MarkitdownError> {
+        let encoding = self
+            .header("content-transfer-encoding")
+            .unwrap_or("7bit")
+            .to_lowercase();
+        match encoding.as_str() {
+            "quoted-printable" => Ok(decode_quoted_printable(&self.body)),
+            "base64" => {
+                let data: Vec<u8> = self
+                    .body
+                    .iter()
+                    .copied()
+                    .filter(|b| !b.is_ascii_whitespace())
+                    .collect();
+                base64::engine::general_purpose::STANDARD
+                    .decode(data)
+                    .map_err(|e| MarkitdownError::ParseError(format!("Invalid base64 part: {}", e)))
+            }
+            _ => Ok(self.body.clone()),
+        }
+    }
+
+    /// The decoded body as text in the part's declared charset.
+    fn text(&self) -> Result<String, MarkitdownError> {
+        let body = self.decoded_body()?;
+        let encoding = self
+            .parameter("charset")
+            .and_then(|charset| Encoding::for_label(charset.as_bytes()))
+            .unwrap_or(encoding_rs::UTF_8);
+        Ok(encoding.decode(&body).0.into_owned())
+    }
+}
+
+/// Bodies between the `--boundary` delimiter lines, without the line break
+/// that belongs to each delimiter.
+fn split_multipart<'a>(body: &'a [u8], boundary: &str) -> Vec<&'a [u8]> {
+    let delimiter = format!("--{}", boundary);
+    let mut parts = Vec::new();
+    let mut start: Option<usize> = None;
+    let mut offset = 0;
+    for line in body.split_inclusive(|&b| b == b'\n') {
+        let trimmed = trim_line_end(line);
+        if let Some(rest) = trimmed.strip_prefix(delimiter.as_bytes()) {
+            let is_close = rest.starts_with(b"--");
+            if is_close || rest.iter().all(u8::is_ascii_whitespace) {
+                if let Some(start) = start {
+                    parts.push(trim_line_end(&body[start..offset]));
+                }
+                if is_close {
+                    return parts;
+                }
+                start = Some(offset + line.len());
+            }
+        }
+        offset += line.len();
+    }
+    // Archives cut short lack the closing delimiter.
+    if let Some(start) = start {
+        parts.push(&body[start..]);
+    }
+    parts
+}
+
+fn trim_line_end(line: &[u8]) -> &[u8] {
+    let line = line.strip_suffix(b"\n").unwrap_or(line);
+    line.strip_suffix(b"\r").unwrap_or(line)
+}
+
+fn decode_quoted_printable(bytes: &[u8]) -> Vec<u8> {
+    let mut decoded = Vec::with_capacity(bytes.len());
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'=' {
+            decoded.push(bytes[i]);
+            i += 1;
+            continue;
+        }
+        // `=` at the end of a line is a soft line break.
+        if bytes[i + 1..].starts_with(b"\r\n") {
+            i += 3;
+        } else if bytes[i + 1..].starts_with(b"\n") {
+            i += 2;
+        } else if let Some(byte) = bytes
+            .get(i + 1..i + 3)
+            .and_then(|hex| std::str::from_utf8(hex).ok())
+            .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+        {
+            decoded.push(byte);
+            i += 3;
+        } else {
+            decoded.push(b'=');
+            i += 1;
+        }
+    }
+    decoded
+}
+
+/// Decodes RFC 2047 `=?charset?Q?...?=` and `=?charset?B?...?=` words, which
+/// browsers use for non-ASCII page titles in the `Subject` header.
+fn decode_encoded_words(value: &str) -> String {
+    let mut decoded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find("=?") {
+        let Some(word) = rest[start + 2..]
+            .find("?=")
+            .and_then(|end| decode_encoded_word(&rest[start + 2..start + 2 + end]))
+        else {
+            break;
+        };
+        let before = &rest[..start];
+        // Whitespace between adjacent encoded words is not part of the text.
+        if decoded.is_empty() || !before.trim().is_empty() {
+            decoded.push_str(before);
+        }
+        decoded.push_str(&word.0);
+        rest = &rest[start + 2 + word.1 + 2..];
+    }
+    decoded.push_str(rest);
+    decoded
+}
+
+/// Decodes the inside of an encoded word, returning the text and the
+/// length consumed.
+fn decode_encoded_word(word: &str) -> Option<(String, usize)> {
+    let mut fields = word.splitn(3, '?');
+    let charset = fields.next()?;
+    let encoding = fields.next()?;
+    let text = fields.next()?;
+    let bytes = match encoding {
+        "B" | "b" => base64::engine::general_purpose::STANDARD
+            .decode(text)
+            .ok()?,
+        "Q" | "q" => decode_quoted_printable(text.replace('_', " ").as_bytes()),
+        _ => return None,
+    };
+    let encoding = Encoding::for_label(charset.as_bytes()).unwrap_or(encoding_rs::UTF_8);
+    Some((encoding.decode(&bytes).0.into_owned(), word.len()))
+}
+
+/// Lookup of archive parts by `cid:` URL and by `Content-Location`.
+struct Archive<'a> {
+    by_reference: HashMap<String, &'a Part>,
+}
+
+impl<'a> Archive<'a> {
+    fn new(parts: &'a [Part]) -> Archive<'a> {
+        let mut by_reference = HashMap::new();
+        for part in parts {
+            if let Some(id) = part.content_id() {
+                by_reference.insert(format!("cid:{}", id), part);
+            }
+            if let Some(location) = part.header("content-location") {
+                by_reference.entry(location.to_string()).or_insert(part);
+            }
+        }
+        Archive { by_reference }
+    }
+
+    /// Points `cid:` URLs at the resource's original location and replaces
+    /// frames saved as separate parts with their content.
+    fn resolve_references(&self, document: &Handle, depth: usize) -> Result<(), MarkitdownError> {
+        let mut frames = Vec::new();
+        walk(document, &mut |node| {
+            if let NodeData::Element { attrs, .. } = &node.data {
+                for attribute in attrs.borrow_mut().iter_mut() {
+                    if !URL_ATTRIBUTES.contains(&&*attribute.name.local) {
+                        continue;
+                    }
+                    let location = self
+                        .by_reference
+                        .get(&*attribute.value)
+                        .and_then(|part| part.header("content-location"));
+                    if let Some(location) = location {
+                        if attribute.value.starts_with("cid:") {
+                            attribute.value = StrTendril::from_slice(location);
+                        }
+                    }
+                }
+            }
+            if matches!(element_name(node), Some("iframe" | "frame")) {
+                frames.push(node.clone());
+            }
+        });
+
+        if depth >= MAX_FRAME_DEPTH {
+            return Ok(());
+        }
+        for frame in frames {
+            let Some(part) = attr(&frame, "src")
+                .and_then(|src| self.by_reference.get(&src).copied())
+                .filter(|part| part.mime_type() == "text/html")
+            else {
+                continue;
+            };
+            let frame_document = parse_html(&part.text()?);
+            self.resolve_references(&frame_document, depth + 1)?;
+            if let Some(body) =
+                find_element(&frame_document, &|node| element_name(node) == Some("body"))
+            {
+                replace_node(&frame, &body);
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Moves `replacement` out of its own tree to where `node` is. It has to be
+/// detached first: dropping a DOM empties every node still attached to it.
+fn replace_node(node: &Handle, replacement: &Handle) {
+    if let Some(old_parent) = replacement.parent.take().and_then(|weak| weak.upgrade()) {
+        old_parent
+            .children
+            .borrow_mut()
+            .retain(|child| !Rc::ptr_eq(child, replacement));
+    }
+    let weak = node.parent.take();
+    let Some(parent) = weak.as_ref().and_then(|weak| weak.upgrade()) else {
+        return;
+    };
+    for child in parent.children.borrow_mut().iter_mut() {
+        if Rc::ptr_eq(child, node) {
+            *child = replacement.clone();
+        }
+    }
+    replacement.parent.set(weak);
+}
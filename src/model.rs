@@ -1,18 +1,226 @@
-use serde::{Deserialize, Serialize};
 use crate::error::MarkitdownError;
+use crate::llm::LlmClient;
+use crate::ocr::OcrEngine;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::sync::Arc;
 
-#[derive(Debug, Serialize, Deserialize)]
+#[derive(Debug, Default, Serialize, Deserialize)]
 pub struct DocumentConverterResult {
     pub title: Option<String>,
     pub text_content: String,
+    /// Problems that were recovered from while converting, such as skipped
+    /// or malformed records.
+    #[serde(default)]
+    pub warnings: Vec<ConversionWarning>,
+    /// Document properties such as `description`, `author` or `language`,
+    /// keyed by name.
+    #[serde(default)]
+    pub metadata: BTreeMap<String, String>,
+    /// Text of each converted page, for paged formats such as PDF.
+    #[serde(default)]
+    pub pages: Vec<Page>,
+    /// Fields of a fillable form, such as an AcroForm in a PDF.
+    #[serde(default)]
+    pub form_fields: Vec<FormField>,
+    /// Text recognised by OCR in scanned pages and images.
+    #[serde(default)]
+    pub ocr_lines: Vec<OcrLine>,
+}
+
+/// The text of a single page of a paged document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Page {
+    /// 1-based page number in the source document.
+    pub number: u32,
+    pub text: String,
+}
+
+/// A form field and the value filled in.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct FormField {
+    /// Fully qualified name, with the names of parent fields joined by `.`.
+    pub name: String,
+    pub kind: FormFieldKind,
+    /// The entered text, the selected options separated by `, `, the chosen
+    /// radio button or the name of the signer.
+    pub value: Option<String>,
+    /// Whether a checkbox or radio button is on, `None` for other kinds.
+    pub checked: Option<bool>,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum FormFieldKind {
+    Text,
+    Checkbox,
+    RadioButton,
+    ComboBox,
+    ListBox,
+    Signature,
+}
+
+/// A line of text found by OCR and where it was found.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct OcrLine {
+    /// 1-based page number, always 1 for images.
+    pub page: u32,
+    pub text: String,
+    /// Bounding box from the top-left corner, in points on PDF pages and in
+    /// pixels on images.
+    pub left: f64,
+    pub top: f64,
+    pub width: f64,
+    pub height: f64,
+    /// Mean recognition confidence of the words, from 0 to 100.
+    pub confidence: f32,
+}
+
+/// A recoverable problem found in the source document.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct ConversionWarning {
+    /// 1-based line in the source where the problem starts, when known.
+    pub line: Option<u64>,
+    pub kind: WarningKind,
+    pub message: String,
 }
 
-#[derive(Debug, Clone)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum WarningKind {
+    /// A record has a different number of fields than the first record.
+    UnequalLengths,
+    /// Text that is not valid UTF-8 and was decoded lossily.
+    InvalidUtf8,
+    /// A record that could not be read and was skipped.
+    Malformed,
+    /// Text recognition failed for a scanned page or an image.
+    Ocr,
+    /// The language model could not caption an image.
+    Llm,
+}
+
+#[derive(Debug, Clone, Default)]
 pub struct ConversionOptions {
     pub file_extension: Option<String>,
     pub url: Option<String>,
     pub llm_client: Option<String>,
     pub llm_model: Option<String>,
+    /// Model used to caption images, taking precedence over `llm_client`
+    /// and `llm_model`.
+    pub llm: Option<Arc<dyn LlmClient>>,
+    /// Prompt sent with each image, the default asking for a detailed
+    /// caption. `{file_name}`, `{mime_type}`, `{metadata}` and `{context}`
+    /// are replaced by the image's file name, MIME type, metadata summary and
+    /// `llm_context`.
+    pub llm_prompt: Option<String>,
+    /// System message sent before the prompt.
+    pub llm_preamble: Option<String>,
+    /// Sampling temperature, 0.5 when `None`.
+    pub llm_temperature: Option<f64>,
+    /// Upper bound on the length of each description in tokens.
+    pub llm_max_tokens: Option<u64>,
+    /// Provider-specific fields merged into each request, such as
+    /// `{"top_p": 0.9}`. Gemini reads them as a `GenerationConfig`.
+    pub llm_params: Option<serde_json::Value>,
+    /// Text around the image in the document it came from, substituted for
+    /// `{context}` in `llm_prompt`.
+    pub llm_context: Option<String>,
+    /// Show each spreadsheet formula next to its cached value.
+    pub excel_formulas: bool,
+    /// How the cells covered by a merged spreadsheet region are rendered.
+    pub excel_merged_cells: MergedCellMode,
+    /// Restrict spreadsheet output to an Excel Table, a defined name or a
+    /// reference such as `A1:F200` or `Sheet2!B3:D10`.
+    pub excel_range: Option<String>,
+    /// Whether the first CSV record is a header, detected when `None`.
+    pub csv_has_header: Option<bool>,
+    /// Maximum number of CSV data rows rendered before the table is cut off.
+    pub csv_max_rows: Option<usize>,
+    /// CSV field delimiter such as `b';'`, sniffed from the content when `None`.
+    pub csv_delimiter: Option<u8>,
+    /// CSV quote character, sniffed from the content when `None`.
+    pub csv_quote: Option<u8>,
+    /// CSV escape character for quotes inside quoted fields, sniffed when
+    /// `None` and otherwise defaulting to doubled quotes.
+    pub csv_escape: Option<u8>,
+    /// Fail on malformed CSV records instead of recovering and reporting
+    /// them in `DocumentConverterResult::warnings`.
+    pub csv_strict: bool,
+    /// Keep only the main content of HTML pages, dropping navigation,
+    /// banners, sidebars and footers.
+    pub html_main_content: bool,
+    /// Markdown flavour used when rendering HTML.
+    pub html_style: MarkdownStyle,
+    /// Whether links in HTML keep their targets or render as plain text.
+    /// Relative targets are resolved against `url` or the page's `<base>`.
+    pub html_links: LinkMode,
+    /// PDF pages to convert, such as `1-5,9` or `10-` for page 10 onwards.
+    /// All pages are converted when `None`.
+    pub pdf_pages: Option<String>,
+    /// Skip detecting tables in PDFs, which is faster but leaves their cells
+    /// as runs of text.
+    pub pdf_skip_tables: bool,
+    /// Include comments and highlighted passages from PDF annotations as
+    /// quoted notes after the text of their page.
+    pub pdf_annotations: bool,
+    /// List every EXIF field, XMP property and IPTC dataset of an image after
+    /// the summary, including maker notes and thumbnail offsets.
+    pub image_full_metadata: bool,
+    /// Password for encrypted PDFs, either the user or the owner password.
+    pub password: Option<String>,
+    /// Run OCR on images. PDF pages without a text layer are always
+    /// recognised.
+    pub ocr: bool,
+    /// Engine used for OCR, a `TesseractOcr` on `PATH` when `None`.
+    pub ocr_engine: Option<Arc<dyn OcrEngine>>,
+}
+
+/// Markdown syntax choices for rendered HTML.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct MarkdownStyle {
+    pub heading: HeadingStyle,
+    /// Marker of unordered list items: `-`, `*` or `+`.
+    pub bullet: char,
+    /// Emphasis delimiter, `*` or `_`. Strong emphasis doubles it.
+    pub emphasis: char,
+}
+
+impl Default for MarkdownStyle {
+    fn default() -> Self {
+        MarkdownStyle {
+            heading: HeadingStyle::Atx,
+            bullet: '-',
+            emphasis: '*',
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum HeadingStyle {
+    /// `# Title`
+    #[default]
+    Atx,
+    /// Title underlined with `===` or `---`. Levels below 2 fall back to ATX.
+    Setext,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum LinkMode {
+    /// `[text](https://example.com/page)`
+    #[default]
+    Keep,
+    /// Only the link text, without its target.
+    Strip,
+}
+
+/// Rendering of merged spreadsheet regions, which markdown tables cannot span.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum MergedCellMode {
+    /// Repeat the region's value in every cell it covers.
+    #[default]
+    Repeat,
+    /// Keep the value in the top-left cell and leave the covered cells empty,
+    /// as the region reads in the spreadsheet itself.
+    Span,
 }
 
 pub trait DocumentConverter {
@@ -0,0 +1,119 @@
+use std::collections::BTreeMap;
+use std::fmt::Debug;
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+use crate::error::MarkitdownError;
+use crate::model::OcrLine;
+
+/// Recognises text in images, for scanned PDF pages and pictures of text.
+pub trait OcrEngine: Debug + Send + Sync {
+    /// Returns the lines of text in an encoded image such as PNG, JPEG or
+    /// PNM, top to bottom, with their positions in pixels.
+    fn recognize(&self, image: &[u8]) -> Result<Vec<OcrLine>, MarkitdownError>;
+}
+
+/// OCR with a locally installed `tesseract` executable.
+#[derive(Debug, Clone)]
+pub struct TesseractOcr {
+    /// Path or name of the executable, looked up on `PATH` by default.
+    pub command: String,
+    /// Trained languages to use, such as `eng` or `deu+fra`.
+    pub languages: String,
+}
+
+impl Default for TesseractOcr {
+    fn default() -> Self {
+        TesseractOcr {
+            command: "tesseract".to_string(),
+            languages: "eng".to_string(),
+        }
+    }
+}
+
+impl OcrEngine for TesseractOcr {
+    fn recognize(&self, image: &[u8]) -> Result<Vec<OcrLine>, MarkitdownError> {
+        let mut child = Command::new(&self.command)
+            .args(["stdin", "stdout", "-l", &self.languages, "tsv"])
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .spawn()
+            .map_err(|e| {
+                MarkitdownError::Conversion(format!("Failed to run {}: {}", self.command, e))
+            })?;
+
+        // Feed the image from another thread so a full stdout pipe can't
+        // block the write.
+        let mut stdin = child.stdin.take().expect("stdin is piped");
+        let output = std::thread::scope(|scope| {
+            scope.spawn(move || stdin.write_all(image));
+            child.wait_with_output()
+        })?;
+        if !output.status.success() {
+            return Err(MarkitdownError::Conversion(format!(
+                "{} failed: {}",
+                self.command,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(parse_tsv(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Joins the words of tesseract's TSV output into lines.
+fn parse_tsv(tsv: &str) -> Vec<OcrLine> {
+    // Columns: level, page_num, block_num, par_num, line_num, word_num,
+    // left, top, width, height, conf, text.
+    let mut lines: BTreeMap<[u32; 4], (OcrLine, f32, usize)> = BTreeMap::new();
+    for row in tsv.lines().skip(1) {
+        let columns: Vec<&str> = row.splitn(12, '\t').collect();
+        if columns.len() < 12 || columns[0] != "5" {
+            continue;
+        }
+        let text = columns[11].trim();
+        let confidence: f32 = columns[10].parse().unwrap_or(-1.0);
+        if text.is_empty() || confidence < 0.0 {
+            continue;
+        }
+        let number = |index: usize| columns[index].parse::<u32>().unwrap_or(0);
+        let key = [number(1), number(2), number(3), number(4)];
+        let (left, top) = (number(6) as f64, number(7) as f64);
+        let (right, bottom) = (left + number(8) as f64, top + number(9) as f64);
+
+        let (line, total, words) = lines.entry(key).or_insert_with(|| {
+            (
+                OcrLine {
+                    page: 1,
+                    text: String::new(),
+                    left,
+                    top,
+                    width: 0.0,
+                    height: 0.0,
+                    confidence: 0.0,
+                },
+                0.0,
+                0,
+            )
+        });
+        if !line.text.is_empty() {
+            line.text.push(' ');
+        }
+        line.text.push_str(text);
+        let (x0, y0) = (line.left.min(left), line.top.min(top));
+        line.width = (line.left + line.width).max(right) - x0;
+        line.height = (line.top + line.height).max(bottom) - y0;
+        line.left = x0;
+        line.top = y0;
+        *total += confidence;
+        *words += 1;
+    }
+
+    lines
+        .into_values()
+        .map(|(mut line, total, words)| {
+            line.confidence = total / words as f32;
+            line
+        })
+        .collect()
+}
@@ -1,7 +1,19 @@
-use pdf_extract;
+use std::collections::{BTreeSet, HashMap, HashSet};
+use std::sync::Arc;
+
+use lopdf::content::Content;
+use lopdf::{Dictionary, Document, Object, ObjectId, Stream, StringFormat};
+use pdf_extract::{ColorSpace, MediaBox, OutputDev, OutputError, Path, PathOp, Transform};
+use unicode_normalization::UnicodeNormalization;
 
 use crate::error::MarkitdownError;
-use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+use crate::model::{
+    ConversionOptions, ConversionWarning, DocumentConverter, DocumentConverterResult, FormField,
+    FormFieldKind, OcrLine, Page, WarningKind,
+};
+use crate::ocr::{OcrEngine, TesseractOcr};
+use crate::pdf_encryption;
+use crate::table::rows_to_markdown;
 
 pub struct PdfConverter;
 
@@ -22,32 +34,9 @@ impl DocumentConverter for PdfConverter {
         }
 
         let bytes = std::fs::read(local_path)?;
-        let text_content = pdf_extract::extract_text_from_mem(&bytes)
-            .map_err(|e| MarkitdownError::ParseError(format!("Failed to extract text from PDF: {}", e)))?;
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content,
-        })
-
-        // match Document::load(local_path) {
-        //     Ok(doc) => {
-        //         let mut text_content = String::new();
-
-        //         for page_num in 1..=doc.get_pages().len() {
-        //             if let Ok(text) = doc.extract_text(&[page_num.try_into().unwrap()]) {
-        //                 text_content.push_str(&text);
-        //                 text_content.push_str("\n\n");
-        //             }
-        //         }
-
-        //         Some(DocumentConverterResult {
-        //             title: None,
-        //             text_content,
-        //         })
-        //     }
-        //     Err(_) => None,
-        // }
+        pdf_to_markdown(&bytes, &args)
     }
+
     fn convert_bytes(
         &self,
         bytes: &[u8],
@@ -63,11 +52,1957 @@ impl DocumentConverter for PdfConverter {
             }
         }
 
-        let text_content = pdf_extract::extract_text_from_mem(bytes)
-            .map_err(|e| MarkitdownError::ParseError(format!("Failed to extract text from PDF: {}", e)))?;
-        Ok(DocumentConverterResult {
-            title: None,
-            text_content,
+        pdf_to_markdown(bytes, &args)
+    }
+}
+
+fn pdf_to_markdown(
+    bytes: &[u8],
+    args: &Option<ConversionOptions>,
+) -> Result<DocumentConverterResult, MarkitdownError> {
+    let options = args.clone().unwrap_or_default();
+    let doc = pdf_encryption::load(bytes, options.password.as_deref())?;
+    let page_count = doc.get_pages().len() as u32;
+    let selection = match &options.pdf_pages {
+        Some(spec) => parse_page_ranges(spec, page_count).ok_or_else(|| {
+            MarkitdownError::InvalidOption(format!("Invalid page range: {}", spec))
+        })?,
+        None => (1..=page_count).collect(),
+    };
+
+    let page_ids = doc.get_pages();
+    let bookmarks = outline(&doc);
+    let mut layouts = Vec::new();
+    let mut warnings = Vec::new();
+    for &number in &selection {
+        let mut layout = page_layout(&doc, number)?;
+        if layout
+            .glyphs
+            .iter()
+            .all(|glyph| glyph.text.trim().is_empty())
+        {
+            let engine = options
+                .ocr_engine
+                .clone()
+                .unwrap_or_else(|| Arc::new(TesseractOcr::default()));
+            if let Err(e) = recognize_page(&doc, page_ids[&number], &mut layout, engine.as_ref()) {
+                warnings.push(ConversionWarning {
+                    line: None,
+                    kind: WarningKind::Ocr,
+                    message: format!("OCR failed on page {}: {}", number, e),
+                });
+            }
+        }
+        annotate(
+            &doc,
+            page_ids[&number],
+            &mut layout,
+            options.pdf_annotations,
+        );
+        layout.bookmarks = bookmarks
+            .iter()
+            .filter(|bookmark| bookmark.page == number)
+            .cloned()
+            .collect();
+        layouts.push(layout);
+    }
+    let page_texts = layout_pages(&layouts, !options.pdf_skip_tables);
+
+    let mut markdown = String::new();
+    let contents: Vec<String> = bookmarks
+        .iter()
+        .filter(|bookmark| selection.contains(&bookmark.page))
+        .map(|bookmark| {
+            format!(
+                "{}- {} (page {})",
+                "  ".repeat(bookmark.level - 1),
+                bookmark.title,
+                bookmark.page
+            )
+        })
+        .collect();
+    if !contents.is_empty() {
+        markdown.push_str("## Contents\n\n");
+        markdown.push_str(&contents.join("\n"));
+        markdown.push_str("\n\n");
+    }
+    let mut pages = Vec::new();
+    for (layout, text) in layouts.iter().zip(page_texts) {
+        let number = layout.number;
+        markdown.push_str(&format!("<!-- Page {} -->\n\n", number));
+        if !text.is_empty() {
+            markdown.push_str(&text);
+            markdown.push_str("\n\n");
+        }
+        pages.push(Page { number, text });
+    }
+
+    let ocr_lines = layouts
+        .iter()
+        .flat_map(|layout| layout.recognized.iter().cloned())
+        .collect();
+    let form_fields = form_fields(&doc);
+    if !form_fields.is_empty() {
+        let mut rows = vec![vec![
+            "Field".to_string(),
+            "Type".to_string(),
+            "Value".to_string(),
+        ]];
+        for field in &form_fields {
+            let value = match (field.kind, field.checked) {
+                (FormFieldKind::Checkbox, Some(true)) => "Checked".to_string(),
+                (FormFieldKind::Checkbox, _) => "Unchecked".to_string(),
+                _ => field.value.clone().unwrap_or_default(),
+            };
+            rows.push(vec![
+                field.name.clone(),
+                kind_label(field.kind).to_string(),
+                value,
+            ]);
+        }
+        markdown.push_str("## Form Fields\n\n");
+        markdown.push_str(&rows_to_markdown(&rows));
+    }
+
+    Ok(DocumentConverterResult {
+        title: None,
+        text_content: markdown.trim_end().to_string(),
+        warnings,
+        pages,
+        form_fields,
+        ocr_lines,
+        ..Default::default()
+    })
+}
+
+fn page_layout(doc: &Document, number: u32) -> Result<PageLayout, MarkitdownError> {
+    let mut output = LayoutOutput::default();
+    pdf_extract::output_doc_page(doc, &mut output, number).map_err(|e| {
+        MarkitdownError::ParseError(format!(
+            "Failed to extract text from page {}: {}",
+            number, e
+        ))
+    })?;
+    let mut layout = output.page.unwrap_or_default();
+    layout.number = number;
+    Ok(layout)
+}
+
+/// Parses page ranges such as `1-5,9` or `10-` into the page numbers that
+/// exist in the document. Returns `None` for malformed ranges and ranges
+/// starting past the last page.
+fn parse_page_ranges(spec: &str, page_count: u32) -> Option<BTreeSet<u32>> {
+    let mut pages = BTreeSet::new();
+    for range in spec
+        .split(',')
+        .map(str::trim)
+        .filter(|range| !range.is_empty())
+    {
+        let (start, end) = match range.split_once('-') {
+            Some((start, end)) => {
+                let start = start.trim().parse().ok()?;
+                let end = match end.trim() {
+                    "" => page_count,
+                    end => end.parse().ok()?,
+                };
+                (start, end)
+            }
+            None => {
+                let page = range.parse().ok()?;
+                (page, page)
+            }
+        };
+        if start == 0 || start > end || start > page_count {
+            return None;
+        }
+        pages.extend(start..=end.min(page_count));
+    }
+    Some(pages)
+}
+
+/// Reads the document outline, the bookmarks shown beside the pages, in
+/// order. Entries whose destination is not a page of the document are left
+/// out.
+fn outline(doc: &Document) -> Vec<Bookmark> {
+    let page_numbers: HashMap<ObjectId, u32> = doc
+        .get_pages()
+        .into_iter()
+        .map(|(number, id)| (id, number))
+        .collect();
+    let first = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| dictionary(doc, catalog.get(b"Outlines").ok()?))
+        .and_then(|outlines| outlines.get(b"First").ok());
+    let mut bookmarks = Vec::new();
+    outline_items(
+        doc,
+        first,
+        1,
+        &page_numbers,
+        &mut HashSet::new(),
+        &mut bookmarks,
+    );
+    bookmarks
+}
+
+fn outline_items(
+    doc: &Document,
+    first: Option<&Object>,
+    level: usize,
+    page_numbers: &HashMap<ObjectId, u32>,
+    visited: &mut HashSet<ObjectId>,
+    bookmarks: &mut Vec<Bookmark>,
+) {
+    let mut next = first;
+    // Outline items link to each other by reference; broken files can loop.
+    while let Some(Ok(id)) = next.map(Object::as_reference) {
+        if !visited.insert(id) {
+            break;
+        }
+        let Ok(item) = doc.get_dictionary(id) else {
+            break;
+        };
+        let title = item
+            .get(b"Title")
+            .ok()
+            .and_then(|title| text_string(doc, title));
+        let destination = item.get(b"Dest").ok().or_else(|| {
+            let action = dictionary(doc, item.get(b"A").ok()?)?;
+            (action.get(b"S").ok()?.as_name().ok()? == b"GoTo")
+                .then(|| action.get(b"D").ok())
+                .flatten()
+        });
+        let target = destination
+            .and_then(|destination| resolve_destination(doc, destination, page_numbers, 0));
+        if let (Some(title), Some((page, left, top))) = (title, target) {
+            bookmarks.push(Bookmark {
+                level,
+                title,
+                page,
+                left,
+                top,
+            });
+        }
+        outline_items(
+            doc,
+            item.get(b"First").ok(),
+            level + 1,
+            page_numbers,
+            visited,
+            bookmarks,
+        );
+        next = item.get(b"Next").ok();
+    }
+}
+
+/// Resolves an explicit or named destination to its page number and the
+/// left and top coordinates it scrolls to, when given.
+fn resolve_destination(
+    doc: &Document,
+    destination: &Object,
+    page_numbers: &HashMap<ObjectId, u32>,
+    depth: usize,
+) -> Option<(u32, Option<f64>, Option<f64>)> {
+    if depth > 8 {
+        return None;
+    }
+    match resolve(doc, destination)? {
+        Object::Array(items) => {
+            let page = *page_numbers.get(&items.first()?.as_reference().ok()?)?;
+            let coordinate = |index: usize| {
+                items
+                    .get(index)
+                    .and_then(|value| value.as_float().ok())
+                    .map(f64::from)
+            };
+            let (left, top) = match items.get(1).and_then(|kind| kind.as_name().ok()) {
+                Some(b"XYZ") => (coordinate(2), coordinate(3)),
+                Some(b"FitH" | b"FitBH") => (None, coordinate(2)),
+                Some(b"FitR") => (coordinate(2), coordinate(5)),
+                _ => (None, None),
+            };
+            Some((page, left, top))
+        }
+        Object::Dictionary(target) => {
+            resolve_destination(doc, target.get(b"D").ok()?, page_numbers, depth + 1)
+        }
+        Object::String(name, _) | Object::Name(name) => {
+            let target = named_destination(doc, name)?;
+            resolve_destination(doc, target, page_numbers, depth + 1)
+        }
+        _ => None,
+    }
+}
+
+/// Looks a destination name up in the catalog, where older files keep a
+/// dictionary and newer ones a name tree.
+fn named_destination<'a>(doc: &'a Document, name: &[u8]) -> Option<&'a Object> {
+    let catalog = doc.catalog().ok()?;
+    if let Some(target) = catalog
+        .get(b"Dests")
+        .ok()
+        .and_then(|dests| dictionary(doc, dests))
+        .and_then(|dests| dests.get(name).ok())
+    {
+        return Some(target);
+    }
+    let names = dictionary(doc, catalog.get(b"Names").ok()?)?;
+    let tree = dictionary(doc, names.get(b"Dests").ok()?)?;
+    find_name(doc, tree, name, 0)
+}
+
+fn find_name<'a>(
+    doc: &'a Document,
+    node: &'a Dictionary,
+    name: &[u8],
+    depth: usize,
+) -> Option<&'a Object> {
+    if depth > 32 {
+        return None;
+    }
+    if let Some(names) = node
+        .get(b"Names")
+        .ok()
+        .and_then(|names| resolve(doc, names))
+    {
+        for pair in names.as_array().ok()?.chunks(2) {
+            if let [key, value] = pair {
+                if resolve(doc, key).and_then(|key| key.as_str().ok()) == Some(name) {
+                    return Some(value);
+                }
+            }
+        }
+    }
+    let kids = resolve(doc, node.get(b"Kids").ok()?)?.as_array().ok()?;
+    kids.iter()
+        .filter_map(|kid| dictionary(doc, kid))
+        .find_map(|kid| find_name(doc, kid, name, depth + 1))
+}
+
+/// Reads the fields of the interactive form, in the order the form lists
+/// them. Push buttons hold no value and are left out.
+fn form_fields(doc: &Document) -> Vec<FormField> {
+    let fields = doc
+        .catalog()
+        .ok()
+        .and_then(|catalog| dictionary(doc, catalog.get(b"AcroForm").ok()?))
+        .and_then(|form| resolve(doc, form.get(b"Fields").ok()?))
+        .and_then(|fields| fields.as_array().ok());
+    let mut form_fields = Vec::new();
+    let mut visited = HashSet::new();
+    for field in fields.into_iter().flatten() {
+        field_tree(
+            doc,
+            field,
+            &FieldAttributes::default(),
+            &mut visited,
+            &mut form_fields,
+        );
+    }
+    form_fields
+}
+
+/// Field attributes that kids inherit from their parent field.
+#[derive(Default)]
+struct FieldAttributes<'a> {
+    name: Option<String>,
+    kind: Option<&'a [u8]>,
+    flags: i64,
+    value: Option<&'a Object>,
+}
+
+fn field_tree<'a>(
+    doc: &'a Document,
+    field: &'a Object,
+    parent: &FieldAttributes<'a>,
+    visited: &mut HashSet<ObjectId>,
+    form_fields: &mut Vec<FormField>,
+) {
+    if let Ok(id) = field.as_reference() {
+        if !visited.insert(id) {
+            return;
+        }
+    }
+    let Some(field) = dictionary(doc, field) else {
+        return;
+    };
+    let name = match (
+        &parent.name,
+        field.get(b"T").ok().and_then(|name| text_string(doc, name)),
+    ) {
+        (Some(parent), Some(name)) => Some(format!("{}.{}", parent, name)),
+        (parent, name) => name.or_else(|| parent.clone()),
+    };
+    let attributes = FieldAttributes {
+        name,
+        kind: field
+            .get(b"FT")
+            .and_then(Object::as_name)
+            .ok()
+            .or(parent.kind),
+        flags: field
+            .get(b"Ff")
+            .and_then(Object::as_i64)
+            .unwrap_or(parent.flags),
+        value: field.get(b"V").ok().or(parent.value),
+    };
+
+    // Kids with a name are fields of their own, the others are the widgets
+    // that show this field on the pages.
+    let kids: Vec<&Object> = field
+        .get(b"Kids")
+        .ok()
+        .and_then(|kids| resolve(doc, kids))
+        .and_then(|kids| kids.as_array().ok())
+        .map(|kids| {
+            kids.iter()
+                .filter(|kid| dictionary(doc, kid).is_some_and(|kid| kid.has(b"T")))
+                .collect()
+        })
+        .unwrap_or_default();
+    if !kids.is_empty() {
+        for kid in kids {
+            field_tree(doc, kid, &attributes, visited, form_fields);
+        }
+        return;
+    }
+
+    let Some(name) = attributes.name else {
+        return;
+    };
+    let value = attributes.value.and_then(|value| resolve(doc, value));
+    let state = value
+        .and_then(|value| value.as_name().ok())
+        .map(|state| String::from_utf8_lossy(state).into_owned())
+        .filter(|state| state != "Off");
+    let (kind, value, checked) = match attributes.kind {
+        Some(b"Tx") => (
+            FormFieldKind::Text,
+            value.and_then(|value| text_string(doc, value)),
+            None,
+        ),
+        // Push buttons.
+        Some(b"Btn") if attributes.flags & (1 << 16) != 0 => return,
+        Some(b"Btn") if attributes.flags & (1 << 15) != 0 => (
+            FormFieldKind::RadioButton,
+            state.clone(),
+            Some(state.is_some()),
+        ),
+        Some(b"Btn") => (
+            FormFieldKind::Checkbox,
+            state.clone(),
+            Some(state.is_some()),
+        ),
+        Some(b"Ch") => {
+            let kind = if attributes.flags & (1 << 17) != 0 {
+                FormFieldKind::ComboBox
+            } else {
+                FormFieldKind::ListBox
+            };
+            let selected = match value {
+                Some(Object::Array(options)) => {
+                    let options: Vec<String> = options
+                        .iter()
+                        .filter_map(|option| text_string(doc, option))
+                        .collect();
+                    (!options.is_empty()).then(|| options.join(", "))
+                }
+                Some(value) => text_string(doc, value),
+                None => None,
+            };
+            (kind, selected, None)
+        }
+        Some(b"Sig") => {
+            let signer = value
+                .and_then(|value| value.as_dict().ok())
+                .and_then(|signature| signature.get(b"Name").ok())
+                .and_then(|signer| text_string(doc, signer));
+            (FormFieldKind::Signature, signer, None)
+        }
+        _ => return,
+    };
+    form_fields.push(FormField {
+        name,
+        kind,
+        value,
+        checked,
+    });
+}
+
+fn kind_label(kind: FormFieldKind) -> &'static str {
+    match kind {
+        FormFieldKind::Text => "Text",
+        FormFieldKind::Checkbox => "Checkbox",
+        FormFieldKind::RadioButton => "Radio button",
+        FormFieldKind::ComboBox => "Combo box",
+        FormFieldKind::ListBox => "List box",
+        FormFieldKind::Signature => "Signature",
+    }
+}
+
+/// Turns link annotations into markdown links around the text they cover.
+/// With `notes`, comments and highlighted passages become quoted notes.
+fn annotate(doc: &Document, page_id: ObjectId, page: &mut PageLayout, notes: bool) {
+    let annotations = doc.get_page_annotations(page_id).unwrap_or_default();
+    let covered = |page: &PageLayout, areas: &[(f64, f64, f64, f64)]| -> Vec<usize> {
+        page.glyphs
+            .iter()
+            .enumerate()
+            .filter(|(_, glyph)| {
+                let x = glyph.x + glyph.advance * glyph.direction.0 / 2.0;
+                let y = glyph.y - glyph.size * 0.3;
+                !glyph.text.trim().is_empty()
+                    && areas.iter().any(|&(x0, y0, x1, y1)| {
+                        x > x0 - 1.0 && x < x1 + 1.0 && y > y0 - 1.0 && y < y1 + 1.0
+                    })
+            })
+            .map(|(index, _)| index)
+            .collect()
+    };
+
+    if notes {
+        let mut quotes: Vec<(f64, String)> = Vec::new();
+        for annotation in &annotations {
+            let Some(kind) = annotation
+                .get(b"Subtype")
+                .ok()
+                .and_then(|subtype| subtype.as_name().ok())
+                .and_then(|subtype| {
+                    Some(match subtype {
+                        b"Text" | b"FreeText" => "Note",
+                        b"Highlight" => "Highlight",
+                        b"Underline" => "Underline",
+                        b"StrikeOut" => "Strikeout",
+                        b"Squiggly" => "Squiggly",
+                        _ => return None,
+                    })
+                })
+            else {
+                continue;
+            };
+            let string = |key: &[u8]| {
+                annotation
+                    .get(key)
+                    .ok()
+                    .and_then(|value| text_string(doc, value))
+            };
+            let areas = annotation_areas(doc, annotation, page.height);
+            let marked = if kind == "Note" {
+                String::new()
+            } else {
+                let glyphs: Vec<Glyph> = covered(page, &areas)
+                    .into_iter()
+                    .map(|index| page.glyphs[index].clone())
+                    .collect();
+                spans(&glyphs)
+                    .into_iter()
+                    .map(|span| span.text)
+                    .collect::<Vec<_>>()
+                    .join(" ")
+            };
+
+            let mut lines = Vec::new();
+            if !marked.is_empty() {
+                lines.push(format!("\"{}\"", marked));
+            }
+            if let Some(contents) = string(b"Contents") {
+                lines.extend(contents.lines().map(|line| line.trim().to_string()));
+            }
+            if lines.is_empty() {
+                continue;
+            }
+            let author = string(b"T").map_or(String::new(), |author| format!(" ({})", author));
+            lines[0] = format!("**{}{}:** {}", kind, author, lines[0]);
+            let quote = lines
+                .iter()
+                .map(|line| format!("> {}", line).trim_end().to_string())
+                .collect::<Vec<_>>()
+                .join("\n");
+            let top = areas.iter().map(|area| area.1).fold(f64::MAX, f64::min);
+            quotes.push((top, quote));
+        }
+        quotes.sort_by(|a, b| a.0.total_cmp(&b.0));
+        page.notes = quotes.into_iter().map(|(_, quote)| quote).collect();
+    }
+
+    let mut linked = HashSet::new();
+    for annotation in &annotations {
+        let is_link = annotation
+            .get(b"Subtype")
+            .ok()
+            .and_then(|subtype| subtype.as_name().ok())
+            == Some(b"Link");
+        let Some(uri) = annotation
+            .get(b"A")
+            .ok()
+            .and_then(|action| dictionary(doc, action))
+            .filter(|action| {
+                action.get(b"S").ok().and_then(|kind| kind.as_name().ok()) == Some(b"URI")
+            })
+            .and_then(|action| resolve(doc, action.get(b"URI").ok()?)?.as_str().ok())
+            .map(|uri| String::from_utf8_lossy(uri).trim().replace(' ', "%20"))
+            .filter(|uri| is_link && !uri.is_empty())
+        else {
+            continue;
+        };
+        let areas = annotation_areas(doc, annotation, page.height);
+        let indices = covered(page, &areas);
+        let (Some(&first), Some(&last)) = (indices.first(), indices.last()) else {
+            continue;
+        };
+        if !indices.iter().all(|index| linked.insert(*index)) {
+            continue;
+        }
+        page.glyphs[first].text.insert(0, '[');
+        page.glyphs[last].text.push_str(&format!("]({})", uri));
+    }
+}
+
+/// The areas an annotation covers, in layout coordinates as
+/// `(left, top, right, bottom)`: one per quadrilateral of marked text, or
+/// its rectangle.
+fn annotation_areas(
+    doc: &Document,
+    annotation: &Dictionary,
+    height: f64,
+) -> Vec<(f64, f64, f64, f64)> {
+    let numbers = |key: &[u8]| -> Vec<f64> {
+        annotation
+            .get(key)
+            .ok()
+            .and_then(|value| resolve(doc, value))
+            .and_then(|value| value.as_array().ok())
+            .map(|values| {
+                values
+                    .iter()
+                    .filter_map(|value| value.as_float().ok())
+                    .map(f64::from)
+                    .collect()
+            })
+            .unwrap_or_default()
+    };
+    let bounds = |points: &[f64]| {
+        let xs = points.iter().step_by(2);
+        let ys = points.iter().skip(1).step_by(2);
+        let (left, right) = xs.fold((f64::MAX, f64::MIN), |(lo, hi), &x| (lo.min(x), hi.max(x)));
+        let (low, high) = ys.fold((f64::MAX, f64::MIN), |(lo, hi), &y| (lo.min(y), hi.max(y)));
+        (left, height - high, right, height - low)
+    };
+    let quads = numbers(b"QuadPoints");
+    if quads.len() >= 8 {
+        return quads.chunks_exact(8).map(bounds).collect();
+    }
+    let rect = numbers(b"Rect");
+    if rect.len() == 4 {
+        vec![bounds(&rect)]
+    } else {
+        Vec::new()
+    }
+}
+
+fn resolve<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Object> {
+    doc.dereference(object).ok().map(|(_, object)| object)
+}
+
+fn dictionary<'a>(doc: &'a Document, object: &'a Object) -> Option<&'a Dictionary> {
+    resolve(doc, object)?.as_dict().ok()
+}
+
+/// Runs OCR on the images drawn on a page without a text layer, placing the
+/// recognised lines in layout space.
+fn recognize_page(
+    doc: &Document,
+    page_id: ObjectId,
+    page: &mut PageLayout,
+    engine: &dyn OcrEngine,
+) -> Result<(), MarkitdownError> {
+    let content = doc
+        .get_page_content(page_id)
+        .and_then(|content| Content::decode(&content))
+        .map_err(|e| {
+            MarkitdownError::ParseError(format!(
+                "Failed to read content of page {}: {}",
+                page.number, e
+            ))
+        })?;
+
+    // Only the transformation matrix of the graphics state matters here.
+    let mut states = vec![[1.0, 0.0, 0.0, 1.0, 0.0, 0.0]];
+    for operation in &content.operations {
+        let ctm = *states.last().unwrap();
+        match operation.operator.as_str() {
+            "q" => states.push(ctm),
+            "Q" if states.len() > 1 => {
+                states.pop();
+            }
+            "cm" => {
+                let m: Vec<f64> = operation
+                    .operands
+                    .iter()
+                    .filter_map(|operand| operand.as_float().ok().map(f64::from))
+                    .collect();
+                if m.len() == 6 {
+                    *states.last_mut().unwrap() = [
+                        m[0] * ctm[0] + m[1] * ctm[2],
+                        m[0] * ctm[1] + m[1] * ctm[3],
+                        m[2] * ctm[0] + m[3] * ctm[2],
+                        m[2] * ctm[1] + m[3] * ctm[3],
+                        m[4] * ctm[0] + m[5] * ctm[2] + ctm[4],
+                        m[4] * ctm[1] + m[5] * ctm[3] + ctm[5],
+                    ];
+                }
+            }
+            "Do" => {
+                let Some(image) = operation
+                    .operands
+                    .first()
+                    .and_then(|name| name.as_name().ok())
+                    .and_then(|name| page_image(doc, page_id, name))
+                else {
+                    continue;
+                };
+                let Some((file, width, height)) = image_file(doc, image) else {
+                    continue;
+                };
+                // Images fill the unit square with their first row at the top.
+                let left = ctm[4].min(ctm[4] + ctm[0]);
+                let top = page.height - ctm[5].max(ctm[5] + ctm[3]);
+                let scale_x = ctm[0].abs() / width as f64;
+                let scale_y = ctm[3].abs() / height as f64;
+                for mut line in engine.recognize(&file)? {
+                    line.page = page.number;
+                    line.left = left + line.left * scale_x;
+                    line.top = top + line.top * scale_y;
+                    line.width *= scale_x;
+                    line.height *= scale_y;
+                    page.recognized.push(line);
+                }
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+/// Looks up an image XObject by its name in the page resources.
+fn page_image<'a>(doc: &'a Document, page_id: ObjectId, name: &[u8]) -> Option<&'a Stream> {
+    let (resources, inherited) = doc.get_page_resources(page_id).ok()?;
+    let stream = resources
+        .into_iter()
+        .chain(
+            inherited
+                .into_iter()
+                .filter_map(|id| doc.get_dictionary(id).ok()),
+        )
+        .filter_map(|resources| dictionary(doc, resources.get(b"XObject").ok()?))
+        .find_map(|xobjects| xobjects.get(name).ok())
+        .and_then(|xobject| resolve(doc, xobject)?.as_stream().ok())?;
+    let subtype = stream.dict.get(b"Subtype").and_then(Object::as_name).ok()?;
+    (subtype == b"Image").then_some(stream)
+}
+
+/// Encodes an image XObject as a file an OCR engine reads, with its width
+/// and height in pixels. JPEG data is passed on as stored and uncompressed
+/// gray, RGB and CMYK samples become a PNM image.
+fn image_file(doc: &Document, image: &Stream) -> Option<(Vec<u8>, u32, u32)> {
+    let dimension = |key: &[u8]| {
+        let value = image.dict.get(key).and_then(Object::as_i64).ok()?;
+        u32::try_from(value).ok().filter(|&value| value > 0)
+    };
+    let (width, height) = (dimension(b"Width")?, dimension(b"Height")?);
+    let filters = image.filters().unwrap_or_default();
+    match filters.last().map(String::as_str) {
+        Some("DCTDecode" | "JPXDecode") if filters.len() == 1 => {
+            return Some((image.content.clone(), width, height));
+        }
+        Some("DCTDecode" | "JPXDecode" | "CCITTFaxDecode" | "JBIG2Decode") => return None,
+        _ => {}
+    }
+
+    let samples = if filters.is_empty() {
+        image.content.clone()
+    } else {
+        // lopdf refuses to decompress streams marked as images.
+        let mut samples = image.clone();
+        samples.dict.remove(b"Subtype");
+        samples.decompressed_content().ok()?
+    };
+    let mask = image
+        .dict
+        .get(b"ImageMask")
+        .and_then(Object::as_bool)
+        .unwrap_or(false);
+    let components = if mask {
+        1
+    } else {
+        color_components(doc, image.dict.get(b"ColorSpace").ok()?)?
+    };
+    let bits = image
+        .dict
+        .get(b"BitsPerComponent")
+        .and_then(Object::as_i64)
+        .unwrap_or(1);
+    // A decode array of `[1 0 ...]` swaps dark and light.
+    let inverted = image
+        .dict
+        .get(b"Decode")
+        .and_then(Object::as_array)
+        .ok()
+        .and_then(|decode| decode.first()?.as_float().ok())
+        == Some(1.0);
+
+    let (width_usize, height_usize) = (width as usize, height as usize);
+    let mut file = Vec::new();
+    match (bits, components) {
+        (1, 1) => {
+            let row = width_usize.div_ceil(8);
+            let samples = samples.get(..row * height_usize)?;
+            file.extend(format!("P4\n{} {}\n", width, height).bytes());
+            // PBM marks black with 1, PDF with 0.
+            file.extend(
+                samples
+                    .iter()
+                    .map(|&byte| if inverted { byte } else { !byte }),
+            );
+        }
+        (8, 1) => {
+            let samples = samples.get(..width_usize * height_usize)?;
+            file.extend(format!("P5\n{} {}\n255\n", width, height).bytes());
+            file.extend(
+                samples
+                    .iter()
+                    .map(|&byte| if inverted { !byte } else { byte }),
+            );
+        }
+        (8, 3) => {
+            let samples = samples.get(..width_usize * height_usize * 3)?;
+            file.extend(format!("P6\n{} {}\n255\n", width, height).bytes());
+            file.extend(
+                samples
+                    .iter()
+                    .map(|&byte| if inverted { !byte } else { byte }),
+            );
+        }
+        (8, 4) => {
+            let samples = samples.get(..width_usize * height_usize * 4)?;
+            file.extend(format!("P6\n{} {}\n255\n", width, height).bytes());
+            for cmyk in samples.chunks(4) {
+                let black = 255 - cmyk[3] as u16;
+                file.extend(
+                    cmyk[..3]
+                        .iter()
+                        .map(|&ink| ((255 - ink as u16) * black / 255) as u8),
+                );
+            }
+        }
+        _ => return None,
+    }
+    Some((file, width, height))
+}
+
+/// Number of components per pixel in a color space, `None` for indexed and
+/// other color spaces the OCR input can't express.
+fn color_components(doc: &Document, color_space: &Object) -> Option<u8> {
+    let color_space = resolve(doc, color_space)?;
+    let (family, parameters) = match color_space {
+        Object::Name(name) => (name.as_slice(), None),
+        Object::Array(array) => (array.first()?.as_name().ok()?, array.get(1)),
+        _ => return None,
+    };
+    match family {
+        b"DeviceGray" | b"CalGray" | b"G" => Some(1),
+        b"DeviceRGB" | b"CalRGB" | b"RGB" => Some(3),
+        b"DeviceCMYK" | b"CMYK" => Some(4),
+        b"ICCBased" => {
+            let profile = resolve(doc, parameters?)?.as_stream().ok()?;
+            let count = profile.dict.get(b"N").and_then(Object::as_i64).ok()?;
+            u8::try_from(count).ok()
+        }
+        _ => None,
+    }
+}
+
+/// Decodes a PDF text string, which is PDFDocEncoding or UTF-16 with a
+/// byte order mark.
+fn text_string(doc: &Document, object: &Object) -> Option<String> {
+    let object = resolve(doc, object)?;
+    let bytes = object.as_str().ok()?;
+    let text = if bytes.starts_with(b"\xfe\xff") || bytes.starts_with(b"\xef\xbb\xbf") {
+        lopdf::decode_text_string(object).ok()?
+    } else {
+        // lopdf's PDFDocEncoding table drops tabs and line breaks.
+        let mut text = String::new();
+        for part in bytes.split_inclusive(|&byte| matches!(byte, b'\t' | b'\n' | b'\r')) {
+            let (part, end) = match part.split_last() {
+                Some((&end @ (b'\t' | b'\n' | b'\r'), rest)) => (rest, Some(end as char)),
+                _ => (part, None),
+            };
+            let part = Object::String(part.to_vec(), StringFormat::Literal);
+            text.push_str(&lopdf::decode_text_string(&part).ok()?);
+            text.extend(end);
+        }
+        text
+    };
+    let text = text.trim_start_matches('\u{feff}').trim();
+    (!text.is_empty()).then(|| text.to_string())
+}
+
+/// A character drawn on the page, in points from the top-left corner.
+#[derive(Debug, Clone)]
+struct Glyph {
+    x: f64,
+    y: f64,
+    /// Unit vector of the writing direction; `(1, 0)` for horizontal text.
+    direction: (f64, f64),
+    advance: f64,
+    size: f64,
+    text: String,
+}
+
+#[derive(Debug, Default)]
+struct PageLayout {
+    number: u32,
+    width: f64,
+    height: f64,
+    glyphs: Vec<Glyph>,
+    rules: Vec<Rule>,
+    /// Outline entries pointing at this page.
+    bookmarks: Vec<Bookmark>,
+    /// Annotations rendered as markdown quotes, top to bottom.
+    notes: Vec<String>,
+    /// Lines found by OCR when the page has no text layer.
+    recognized: Vec<OcrLine>,
+}
+
+/// An entry of the document outline.
+#[derive(Debug, Clone)]
+struct Bookmark {
+    /// Nesting depth, from 1 for top-level entries.
+    level: usize,
+    title: String,
+    page: u32,
+    /// Where the destination scrolls to, in page space with the origin at
+    /// the bottom left.
+    left: Option<f64>,
+    top: Option<f64>,
+}
+
+/// A horizontal or vertical line drawn on the page, such as a table border.
+#[derive(Debug, Clone, Copy)]
+struct Rule {
+    horizontal: bool,
+    /// The y of horizontal rules, the x of vertical ones.
+    position: f64,
+    start: f64,
+    end: f64,
+}
+
+impl Rule {
+    fn between((x0, y0): (f64, f64), (x1, y1): (f64, f64)) -> Option<Rule> {
+        if (y0 - y1).abs() < RULE_TOLERANCE && (x0 - x1).abs() >= RULE_TOLERANCE {
+            Some(Rule {
+                horizontal: true,
+                position: (y0 + y1) / 2.0,
+                start: x0.min(x1),
+                end: x0.max(x1),
+            })
+        } else if (x0 - x1).abs() < RULE_TOLERANCE && (y0 - y1).abs() >= RULE_TOLERANCE {
+            Some(Rule {
+                horizontal: false,
+                position: (x0 + x1) / 2.0,
+                start: y0.min(y1),
+                end: y0.max(y1),
+            })
+        } else {
+            None
+        }
+    }
+
+    fn crosses(&self, other: &Rule) -> bool {
+        self.horizontal != other.horizontal
+            && other.position > self.start - RULE_TOLERANCE
+            && other.position < self.end + RULE_TOLERANCE
+            && self.position > other.start - RULE_TOLERANCE
+            && self.position < other.end + RULE_TOLERANCE
+    }
+}
+
+/// Collects the positioned glyphs of a page from `pdf_extract`.
+#[derive(Default)]
+struct LayoutOutput {
+    page: Option<PageLayout>,
+}
+
+impl OutputDev for LayoutOutput {
+    fn begin_page(
+        &mut self,
+        _page_num: u32,
+        media_box: &MediaBox,
+        _art_box: Option<(f64, f64, f64, f64)>,
+    ) -> Result<(), OutputError> {
+        self.page = Some(PageLayout {
+            width: media_box.urx - media_box.llx,
+            height: media_box.ury - media_box.lly,
+            ..Default::default()
+        });
+        Ok(())
+    }
+
+    fn end_page(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn output_character(
+        &mut self,
+        trm: &Transform,
+        width: f64,
+        _spacing: f64,
+        font_size: f64,
+        char: &str,
+    ) -> Result<(), OutputError> {
+        let Some(page) = &mut self.page else {
+            return Ok(());
+        };
+        let scale = (trm.m11 * trm.m22 - trm.m12 * trm.m21).abs().sqrt();
+        let length = trm.m11.hypot(trm.m12);
+        if scale == 0.0 || length == 0.0 {
+            return Ok(());
+        }
+        let size = font_size * scale;
+        page.glyphs.push(Glyph {
+            x: trm.m31,
+            y: page.height - trm.m32,
+            // Page space points up, layout space points down.
+            direction: (trm.m11 / length, -trm.m12 / length),
+            advance: width * size,
+            size,
+            text: char.to_string(),
+        });
+        Ok(())
+    }
+
+    fn begin_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_word(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn end_line(&mut self) -> Result<(), OutputError> {
+        Ok(())
+    }
+
+    fn stroke(
+        &mut self,
+        ctm: &Transform,
+        _colorspace: &ColorSpace,
+        _color: &[f64],
+        path: &Path,
+    ) -> Result<(), OutputError> {
+        self.add_rules(ctm, path, false);
+        Ok(())
+    }
+
+    fn fill(
+        &mut self,
+        ctm: &Transform,
+        _colorspace: &ColorSpace,
+        _color: &[f64],
+        path: &Path,
+    ) -> Result<(), OutputError> {
+        self.add_rules(ctm, path, true);
+        Ok(())
+    }
+}
+
+impl LayoutOutput {
+    /// Records the straight horizontal and vertical edges of a path. Filled
+    /// shapes only count when they are thin enough to read as lines.
+    fn add_rules(&mut self, ctm: &Transform, path: &Path, filled: bool) {
+        let Some(page) = &mut self.page else {
+            return;
+        };
+        let height = page.height;
+        let point = |x: f64, y: f64| {
+            (
+                x * ctm.m11 + y * ctm.m21 + ctm.m31,
+                height - (x * ctm.m12 + y * ctm.m22 + ctm.m32),
+            )
+        };
+        let mut edges = Vec::new();
+        let (mut current, mut subpath_start) = ((0.0, 0.0), (0.0, 0.0));
+        for op in &path.ops {
+            match *op {
+                PathOp::MoveTo(x, y) => {
+                    current = point(x, y);
+                    subpath_start = current;
+                }
+                PathOp::LineTo(x, y) => {
+                    let next = point(x, y);
+                    edges.push((current, next));
+                    current = next;
+                }
+                PathOp::CurveTo(_, _, _, _, x, y) => current = point(x, y),
+                PathOp::Rect(x, y, width, height) => {
+                    let corners = [
+                        point(x, y),
+                        point(x + width, y),
+                        point(x + width, y + height),
+                        point(x, y + height),
+                    ];
+                    for i in 0..4 {
+                        edges.push((corners[i], corners[(i + 1) % 4]));
+                    }
+                    current = corners[0];
+                    subpath_start = current;
+                }
+                PathOp::Close => {
+                    edges.push((current, subpath_start));
+                    current = subpath_start;
+                }
+            }
+        }
+
+        if filled {
+            let xs = edges.iter().flat_map(|&((x0, _), (x1, _))| [x0, x1]);
+            let ys = edges.iter().flat_map(|&((_, y0), (_, y1))| [y0, y1]);
+            let (left, right) = xs.fold((f64::MAX, f64::MIN), |(lo, hi), x| (lo.min(x), hi.max(x)));
+            let (top, bottom) = ys.fold((f64::MAX, f64::MIN), |(lo, hi), y| (lo.min(y), hi.max(y)));
+            let middle = ((left + right) / 2.0, (top + bottom) / 2.0);
+            let rule = if bottom - top < RULE_TOLERANCE {
+                Rule::between((left, middle.1), (right, middle.1))
+            } else if right - left < RULE_TOLERANCE {
+                Rule::between((middle.0, top), (middle.0, bottom))
+            } else {
+                None
+            };
+            page.rules.extend(rule);
+        } else {
+            page.rules.extend(
+                edges
+                    .into_iter()
+                    .filter_map(|(from, to)| Rule::between(from, to)),
+            );
+        }
+    }
+}
+
+/// Glyphs written next to each other on one baseline. Spans are split at
+/// gaps wider than a few spaces, such as column gutters.
+#[derive(Debug, Clone)]
+struct Span {
+    x0: f64,
+    x1: f64,
+    y: f64,
+    size: f64,
+    text: String,
+    /// Rotated text such as margin stamps, kept out of the reading order.
+    rotated: bool,
+    /// Cells of a ruled table standing in for the glyphs it contains.
+    table: Option<Vec<Vec<String>>>,
+}
+
+/// A line of text within one column, or a table read as a single unit.
+#[derive(Debug, Clone)]
+struct Line {
+    x0: f64,
+    x1: f64,
+    y: f64,
+    size: f64,
+    text: String,
+    table: Option<Vec<Vec<String>>>,
+}
+
+/// Consecutive lines of one column that belong together, or a table.
+#[derive(Debug)]
+struct Block {
+    x0: f64,
+    /// Baseline of the first line.
+    y: f64,
+    size: f64,
+    text: String,
+    table: Option<Vec<Vec<String>>>,
+    /// Heading level given by a bookmark, overriding the font size.
+    heading: Option<usize>,
+}
+
+/// Gap between glyphs, relative to the font size, that reads as a space.
+const SPACE_GAP: f64 = 0.15;
+
+/// Gap, relative to the font size, that ends a span.
+const SPAN_GAP: f64 = 1.5;
+
+/// Share of the page height at the top and bottom that may hold running
+/// headers, footers and page numbers.
+const MARGIN_ZONE: f64 = 0.1;
+
+/// Font size, relative to body text, from which a short block is a heading.
+const HEADING_RATIO: f64 = 1.15;
+
+/// Longest text treated as a heading.
+const MAX_HEADING_LEN: usize = 150;
+
+/// Distance in points within which line ends meet and a line counts as
+/// straight.
+const RULE_TOLERANCE: f64 = 2.0;
+
+/// Fewest consecutive aligned rows read as a table without ruling lines.
+const MIN_TABLE_ROWS: usize = 3;
+
+/// Longest average cell text in a table without ruling lines. Lines of
+/// running text are longer.
+const MAX_CELL_LEN: usize = 30;
+
+/// Reconstructs the text of each page as markdown paragraphs and headings,
+/// in reading order and without running headers, footers and page numbers.
+fn layout_pages(layouts: &[PageLayout], detect_tables: bool) -> Vec<String> {
+    let mut page_spans: Vec<Vec<Span>> = layouts
+        .iter()
+        .map(|page| {
+            let mut spans = if detect_tables {
+                let (mut tables, glyphs) = ruled_tables(page);
+                tables.extend(spans(&glyphs));
+                tables
+            } else {
+                spans(&page.glyphs)
+            };
+            spans.extend(page.recognized.iter().map(|line| Span {
+                x0: line.left,
+                x1: line.left + line.width,
+                y: line.top + line.height,
+                size: line.height,
+                text: line.text.clone(),
+                rotated: false,
+                table: None,
+            }));
+            spans
+        })
+        .collect();
+    remove_running_lines(&mut page_spans, layouts);
+
+    let page_blocks: Vec<(Vec<Block>, Vec<Block>)> = page_spans
+        .into_iter()
+        .zip(layouts)
+        .map(|(spans, page)| {
+            let (rotated, spans): (Vec<Span>, Vec<Span>) =
+                spans.into_iter().partition(|span| span.rotated);
+            let stamps = rotated
+                .into_iter()
+                .map(|span| Block {
+                    x0: span.x0,
+                    y: span.y,
+                    size: span.size,
+                    text: span.text,
+                    table: None,
+                    heading: None,
+                })
+                .collect();
+            let blocks = reading_order(spans, page.width)
+                .into_iter()
+                .flat_map(|flow| blocks(&lines(flow, detect_tables)))
+                .collect();
+            (stamps, blocks)
+        })
+        .collect();
+
+    let body_size = body_size(page_blocks.iter().flat_map(|(_, blocks)| blocks));
+    let heading_sizes = heading_sizes(page_blocks.iter().flat_map(|(_, blocks)| blocks), body_size);
+
+    page_blocks
+        .into_iter()
+        .zip(layouts)
+        .map(|((stamps, mut blocks), page)| {
+            place_bookmarks(&mut blocks, page);
+            let mut paragraphs: Vec<String> = stamps.into_iter().map(|stamp| stamp.text).collect();
+            for block in blocks {
+                if let Some(rows) = &block.table {
+                    paragraphs.push(rows_to_markdown(rows).trim_end().to_string());
+                    continue;
+                }
+                let level = block
+                    .heading
+                    .or_else(|| heading_level(&block, body_size, &heading_sizes));
+                paragraphs.push(match level {
+                    Some(level) => format!("{} {}", "#".repeat(level), block.text),
+                    None => block.text,
+                });
+            }
+            paragraphs.extend(page.notes.iter().cloned());
+            paragraphs.join("\n\n")
+        })
+        .collect()
+}
+
+/// Groups glyphs, in content stream order, into spans.
+fn spans(glyphs: &[Glyph]) -> Vec<Span> {
+    let mut spans: Vec<Span> = Vec::new();
+    // End of the previous glyph, to measure the gap to the next one.
+    let mut end: Option<(f64, f64, (f64, f64))> = None;
+    for glyph in glyphs {
+        let rotated = glyph.direction.0 < 0.99;
+        let continues = end.and_then(|(end_x, end_y, direction)| {
+            if direction != glyph.direction {
+                return None;
+            }
+            let size = spans.last()?.size.max(glyph.size);
+            let (dx, dy) = (glyph.x - end_x, glyph.y - end_y);
+            let along = dx * direction.0 + dy * direction.1;
+            let across = dy * direction.0 - dx * direction.1;
+            let same_line = across.abs() < size * 0.5;
+            (same_line && along > -size && along < size * SPAN_GAP).then_some(along / size)
+        });
+        let is_space = glyph.text.trim().is_empty();
+        match (continues, spans.last_mut()) {
+            (Some(gap), Some(span)) => {
+                // TeX sets accents as separate glyphs overlapping the letter.
+                let overlaps = gap < -0.2;
+                if let Some(mark) = combining_mark(&glyph.text).filter(|_| overlaps) {
+                    span.text.push(mark);
+                } else if let Some(mark) = span
+                    .text
+                    .chars()
+                    .next_back()
+                    .and_then(|accent| combining_mark(&accent.to_string()))
+                    .filter(|_| overlaps && !is_space)
+                {
+                    span.text.pop();
+                    span.text.push_str(&glyph.text);
+                    span.text.push(mark);
+                } else {
+                    if (is_space || gap > SPACE_GAP) && !span.text.ends_with(' ') {
+                        span.text.push(' ');
+                    }
+                    if !is_space {
+                        span.text.push_str(&glyph.text);
+                    }
+                }
+                span.x1 = span.x1.max(glyph.x + glyph.advance * glyph.direction.0);
+                // Superscripts and subscripts keep the baseline of the body.
+                if glyph.size >= span.size {
+                    span.size = glyph.size;
+                    span.y = glyph.y;
+                }
+            }
+            _ if is_space => {}
+            _ => spans.push(Span {
+                x0: glyph.x,
+                x1: glyph.x + glyph.advance * glyph.direction.0,
+                y: glyph.y,
+                size: glyph.size,
+                text: glyph.text.clone(),
+                rotated,
+                table: None,
+            }),
+        }
+        end = Some((
+            glyph.x + glyph.advance * glyph.direction.0,
+            glyph.y + glyph.advance * glyph.direction.1,
+            glyph.direction,
+        ));
+    }
+    for span in &mut spans {
+        span.text = span.text.trim().nfc().collect();
+    }
+    spans.retain(|span| !span.text.is_empty());
+    spans
+}
+
+/// The combining form of a spacing accent.
+fn combining_mark(accent: &str) -> Option<char> {
+    Some(match accent {
+        "´" => '\u{301}',
+        "`" => '\u{300}',
+        "¨" => '\u{308}',
+        "ˆ" | "^" => '\u{302}',
+        "˜" | "~" => '\u{303}',
+        "¸" => '\u{327}',
+        "ˇ" => '\u{30c}',
+        "˚" => '\u{30a}',
+        _ => return None,
+    })
+}
+
+/// Drops page numbers and the lines repeated at the same place across pages,
+/// such as running titles and copyright footers.
+fn remove_running_lines(page_spans: &mut [Vec<Span>], layouts: &[PageLayout]) {
+    let margin_key = |span: &Span, height: f64| -> Option<String> {
+        if span.table.is_some() {
+            return None;
+        }
+        let zone = if span.y < height * MARGIN_ZONE {
+            "top"
+        } else if span.y > height * (1.0 - MARGIN_ZONE) {
+            "bottom"
+        } else {
+            return None;
+        };
+        // Running lines often contain the page number or a date.
+        let text: String = span
+            .text
+            .to_lowercase()
+            .chars()
+            .map(|c| if c.is_ascii_digit() { '#' } else { c })
+            .collect();
+        Some(format!("{} {}", zone, text))
+    };
+
+    let mut counts: HashMap<String, usize> = HashMap::new();
+    for (spans, page) in page_spans.iter().zip(layouts) {
+        let keys: HashSet<String> = spans
+            .iter()
+            .filter_map(|span| margin_key(span, page.height))
+            .collect();
+        for key in keys {
+            *counts.entry(key).or_default() += 1;
+        }
+    }
+    let threshold = (layouts.len() / 2).max(2);
+    for (spans, page) in page_spans.iter_mut().zip(layouts) {
+        spans.retain(|span| {
+            let Some(key) = margin_key(span, page.height) else {
+                return true;
+            };
+            !is_page_number(&span.text) && counts.get(&key).copied().unwrap_or(0) < threshold
+        });
+    }
+}
+
+/// Recognises `7`, `- 7 -`, `Page 7`, `7 of 12`, `7 / 12` and roman numerals.
+fn is_page_number(text: &str) -> bool {
+    let text = text.trim_matches(|c: char| c == '-' || c == '–' || c.is_whitespace());
+    let text = text.to_lowercase();
+    let text = text.strip_prefix("page").unwrap_or(&text).trim();
+    let number = text
+        .split_once(" of ")
+        .or_else(|| text.split_once('/'))
+        .map_or(text, |(number, _)| number.trim());
+    !number.is_empty()
+        && (number.chars().all(|c| c.is_ascii_digit())
+            || number.chars().all(|c| "ivxlc".contains(c)))
+}
+
+/// Splits the spans of a page into flows read one after another: full-width
+/// text, and the left then the right column of two-column sections.
+fn reading_order(mut spans: Vec<Span>, page_width: f64) -> Vec<Vec<Span>> {
+    spans.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x0.total_cmp(&b.x0)));
+    let Some(gutter) = find_gutter(&spans, page_width) else {
+        return vec![spans];
+    };
+
+    let mut flows = Vec::new();
+    let (mut full, mut left, mut right) = (Vec::new(), Vec::new(), Vec::new());
+    for span in spans {
+        if span.x1 <= gutter || span.x0 >= gutter {
+            if !full.is_empty() {
+                flows.push(std::mem::take(&mut full));
+            }
+            if span.x1 <= gutter {
+                left.push(span);
+            } else {
+                right.push(span);
+            }
+        } else {
+            if !left.is_empty() || !right.is_empty() {
+                flows.push(std::mem::take(&mut left));
+                flows.push(std::mem::take(&mut right));
+            }
+            full.push(span);
+        }
+    }
+    flows.push(full);
+    flows.push(left);
+    flows.push(right);
+    flows.retain(|flow| !flow.is_empty());
+    flows
+}
+
+/// The x position of an empty vertical band in the middle of the page
+/// separating two columns of text.
+fn find_gutter(spans: &[Span], page_width: f64) -> Option<f64> {
+    let crossings = |x: f64| {
+        spans
+            .iter()
+            .filter(|span| span.x0 < x && span.x1 > x)
+            .count()
+    };
+    let candidates: Vec<(f64, usize)> = (0..=40)
+        .map(|step| {
+            let x = page_width * (0.3 + 0.01 * step as f64);
+            (x, crossings(x))
+        })
+        .collect();
+    let fewest = candidates.iter().map(|&(_, count)| count).min()?;
+    if fewest * 10 > spans.len() {
+        return None;
+    }
+
+    // The middle of the longest run of candidates crossing the fewest spans.
+    let mut best: Option<(usize, usize)> = None;
+    let mut start = None;
+    for (i, &(_, count)) in candidates.iter().enumerate() {
+        if count == fewest {
+            let run_start = *start.get_or_insert(i);
+            if best.is_none_or(|(from, to)| i - run_start > to - from) {
+                best = Some((run_start, i));
+            }
+        } else {
+            start = None;
+        }
+    }
+    let (from, to) = best?;
+    let gutter = (candidates[from].0 + candidates[to].0) / 2.0;
+    // Columns hold running text, not short labels and values side by side.
+    let is_column = |side: Vec<&Span>| {
+        let width: f64 = side.iter().map(|span| span.x1 - span.x0).sum();
+        side.len() >= 3 && width / side.len() as f64 > page_width * 0.2
+    };
+    let left = spans.iter().filter(|span| span.x1 <= gutter).collect();
+    let right = spans.iter().filter(|span| span.x0 >= gutter).collect();
+    (is_column(left) && is_column(right)).then_some(gutter)
+}
+
+/// Joins spans sorted top to bottom into lines, reading runs of rows whose
+/// spans line up in columns as tables.
+fn lines(spans: Vec<Span>, detect_tables: bool) -> Vec<Line> {
+    let mut rows: Vec<Vec<Span>> = Vec::new();
+    for span in spans {
+        let same_row = rows
+            .last()
+            .and_then(|row| row.first())
+            .is_some_and(|first| {
+                first.table.is_none()
+                    && span.table.is_none()
+                    && (span.y - first.y).abs() < first.size.max(span.size) * 0.5
+            });
+        match rows.last_mut() {
+            Some(row) if same_row => row.push(span),
+            _ => rows.push(vec![span]),
+        }
+    }
+    for row in &mut rows {
+        row.sort_by(|a, b| a.x0.total_cmp(&b.x0));
+    }
+
+    let mut lines = Vec::new();
+    let mut i = 0;
+    while i < rows.len() {
+        if detect_tables {
+            if let Some((table, end)) = aligned_table(&rows, i) {
+                lines.push(table);
+                i = end;
+                continue;
+            }
+        }
+        lines.push(join_row(&rows[i]));
+        i += 1;
+    }
+    lines
+}
+
+/// Reads the rows from `start` as a table when at least
+/// [`MIN_TABLE_ROWS`] consecutive rows split into the same columns. Returns
+/// the table and the index of the first row after it.
+fn aligned_table(rows: &[Vec<Span>], start: usize) -> Option<(Line, usize)> {
+    let is_row = |row: &Vec<Span>| row.len() >= 2 && row.iter().all(|span| span.table.is_none());
+    let mut end = start;
+    while end < rows.len() && is_row(&rows[end]) {
+        let close = end == start || {
+            let (previous, row) = (&rows[end - 1][0], &rows[end][0]);
+            row.y - previous.y < previous.size.max(row.size) * 2.5
+        };
+        if !close {
+            break;
+        }
+        end += 1;
+    }
+    if end - start < MIN_TABLE_ROWS {
+        return None;
+    }
+
+    // Columns are the separate stretches covered by the spans of all rows.
+    let mut extents: Vec<(f64, f64)> = rows[start..end]
+        .iter()
+        .flatten()
+        .map(|span| (span.x0, span.x1))
+        .collect();
+    extents.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let mut columns: Vec<(f64, f64)> = Vec::new();
+    for (x0, x1) in extents {
+        match columns.last_mut() {
+            Some(column) if x0 < column.1 => column.1 = column.1.max(x1),
+            _ => columns.push((x0, x1)),
+        }
+    }
+    if columns.len() < 2 {
+        return None;
+    }
+
+    let cells: Vec<Vec<String>> = rows[start..end]
+        .iter()
+        .map(|row| {
+            columns
+                .iter()
+                .map(|&(x0, x1)| {
+                    row.iter()
+                        .filter(|span| span.x0 >= x0 && span.x1 <= x1)
+                        .map(|span| span.text.as_str())
+                        .collect::<Vec<_>>()
+                        .join(" ")
+                })
+                .collect()
         })
+        .collect();
+    // Side-by-side text columns and chart axes are not tables.
+    let texts: Vec<&String> = cells
+        .iter()
+        .flatten()
+        .filter(|cell| !cell.is_empty())
+        .collect();
+    let length: usize = texts.iter().map(|cell| cell.chars().count()).sum();
+    if texts.len() * 2 < cells.len() * columns.len() || length > texts.len() * MAX_CELL_LEN {
+        return None;
+    }
+
+    let first = &rows[start][0];
+    let table = Line {
+        x0: columns[0].0,
+        x1: columns[columns.len() - 1].1,
+        y: first.y,
+        size: first.size,
+        text: String::new(),
+        table: Some(cells),
+    };
+    Some((table, end))
+}
+
+fn join_row(row: &[Span]) -> Line {
+    if let [span] = row {
+        if let Some(table) = &span.table {
+            return Line {
+                x0: span.x0,
+                x1: span.x1,
+                y: span.y,
+                size: span.size,
+                text: String::new(),
+                table: Some(table.clone()),
+            };
+        }
+    }
+    let text = row
+        .iter()
+        .map(|span| span.text.as_str())
+        .collect::<Vec<_>>()
+        .join(" ");
+    let main = row
+        .iter()
+        .max_by(|a, b| a.size.total_cmp(&b.size))
+        .expect("rows are not empty");
+    Line {
+        x0: row[0].x0,
+        x1: row.iter().map(|span| span.x1).fold(f64::MIN, f64::max),
+        y: main.y,
+        size: main.size,
+        text,
+        table: None,
+    }
+}
+
+/// Joins the lines of a flow into paragraphs, breaking at wide vertical
+/// gaps, font size changes, indented first lines and short last lines.
+fn blocks(lines: &[Line]) -> Vec<Block> {
+    let left = lines.iter().map(|line| line.x0).fold(f64::MAX, f64::min);
+    let right = lines.iter().map(|line| line.x1).fold(f64::MIN, f64::max);
+    let mut blocks: Vec<Block> = Vec::new();
+    let mut previous: Option<&Line> = None;
+    for line in lines {
+        if line.table.is_some() {
+            blocks.push(Block {
+                x0: line.x0,
+                y: line.y,
+                size: line.size,
+                text: String::new(),
+                table: line.table.clone(),
+                heading: None,
+            });
+            previous = None;
+            continue;
+        }
+        let new_block = previous.is_none_or(|previous| {
+            let size = previous.size.max(line.size);
+            let gap = line.y - previous.y;
+            gap > size * 1.6
+                || gap < 0.0
+                || (line.size - previous.size).abs() > previous.size * 0.1
+                || is_indented(line, previous, left, right)
+                || (previous.x1 < right - size * 4.0 && ends_sentence(&previous.text))
+        });
+        match blocks.last_mut() {
+            Some(block) if !new_block => join_line(&mut block.text, &line.text),
+            _ => blocks.push(Block {
+                x0: line.x0,
+                y: line.y,
+                size: line.size,
+                text: line.text.clone(),
+                table: None,
+                heading: None,
+            }),
+        }
+        previous = Some(line);
+    }
+    blocks
+}
+
+/// A full-width line indented from the margin starts a paragraph. Centred
+/// lines are also indented but stop short of the right margin.
+fn is_indented(line: &Line, previous: &Line, left: f64, right: f64) -> bool {
+    let size = line.size;
+    line.x0 > left + size * 0.5
+        && line.x0 < left + size * 4.0
+        && line.x0 > previous.x0 + size * 0.5
+        && line.x1 > right - size * 2.0
+}
+
+fn ends_sentence(text: &str) -> bool {
+    text.ends_with(['.', ':', '!', '?'])
+}
+
+/// Appends a line to a paragraph, rejoining words hyphenated at the line end.
+fn join_line(paragraph: &mut String, line: &str) {
+    let hyphenated = paragraph
+        .strip_suffix(['-', '\u{ad}'])
+        .and_then(|rest| rest.chars().next_back())
+        .is_some_and(char::is_alphabetic);
+    if hyphenated && line.starts_with(char::is_lowercase) {
+        paragraph.pop();
+    } else {
+        paragraph.push(' ');
+    }
+    paragraph.push_str(line);
+}
+
+/// The font size most of the text is set in.
+fn body_size<'a>(blocks: impl Iterator<Item = &'a Block>) -> f64 {
+    let mut weights: HashMap<i64, usize> = HashMap::new();
+    for block in blocks.filter(|block| block.table.is_none()) {
+        *weights.entry(size_key(block.size)).or_default() += block.text.len();
+    }
+    weights
+        .into_iter()
+        .max_by_key(|&(size, weight)| (weight, -size))
+        .map_or(0.0, |(size, _)| size as f64 / 2.0)
+}
+
+/// Heading font sizes in the document, largest first.
+fn heading_sizes<'a>(blocks: impl Iterator<Item = &'a Block>, body_size: f64) -> Vec<i64> {
+    let mut sizes: Vec<i64> = blocks
+        .filter(|block| is_heading(block, body_size))
+        .map(|block| size_key(block.size))
+        .collect();
+    sizes.sort_unstable_by(|a, b| b.cmp(a));
+    sizes.dedup();
+    sizes
+}
+
+fn is_heading(block: &Block, body_size: f64) -> bool {
+    block.table.is_none()
+        && block.size >= body_size * HEADING_RATIO
+        && block.text.len() <= MAX_HEADING_LEN
+        && !block.text.ends_with(['.', ','])
+}
+
+fn heading_level(block: &Block, body_size: f64, heading_sizes: &[i64]) -> Option<usize> {
+    if !is_heading(block, body_size) {
+        return None;
+    }
+    let index = heading_sizes
+        .iter()
+        .position(|&size| size == size_key(block.size))?;
+    Some((index + 1).min(6))
+}
+
+/// Turns the blocks that the bookmarks of a page point at into headings one
+/// level below the bookmark depth, so that `#` stays free for the title.
+/// Bookmarks whose title is not on the page become headings of their own at
+/// their destination.
+fn place_bookmarks(blocks: &mut Vec<Block>, page: &PageLayout) {
+    for bookmark in &page.bookmarks {
+        let level = (bookmark.level + 1).min(6);
+        let title = heading_key(&bookmark.title);
+        if let Some(block) = blocks.iter_mut().find(|block| {
+            block.table.is_none() && block.heading.is_none() && heading_key(&block.text) == title
+        }) {
+            block.heading = Some(level);
+            continue;
+        }
+        let top = bookmark.top.map(|top| page.height - top);
+        let index = blocks
+            .iter()
+            .position(|block| {
+                top.is_none_or(|top| block.y >= top - block.size)
+                    && bookmark
+                        .left
+                        .is_none_or(|left| block.x0 >= left - block.size)
+            })
+            .unwrap_or(blocks.len());
+        blocks.insert(
+            index,
+            Block {
+                x0: bookmark.left.unwrap_or(0.0),
+                y: top.unwrap_or(0.0),
+                size: 0.0,
+                text: bookmark.title.clone(),
+                table: None,
+                heading: Some(level),
+            },
+        );
+    }
+}
+
+/// Heading text compared case-insensitively, without section numbers such
+/// as `2.1`, `IV.` or `B` and without punctuation.
+fn heading_key(text: &str) -> String {
+    let is_number = |word: &str| {
+        let word = word.trim_end_matches('.').to_lowercase();
+        word.chars().all(|c| c.is_ascii_digit() || c == '.')
+            || word.chars().all(|c| "ivxlc".contains(c))
+            || word.chars().count() == 1
+    };
+    let words: Vec<&str> = text.split_whitespace().collect();
+    let numbering = words.iter().take_while(|word| is_number(word)).count();
+    words[numbering.min(words.len().saturating_sub(1))..]
+        .iter()
+        .flat_map(|word| word.chars().filter(|c| c.is_alphanumeric()).chain([' ']))
+        .collect::<String>()
+        .to_lowercase()
+        .trim_end()
+        .to_string()
+}
+
+/// Font sizes rounded to half points, to compare sizes across pages.
+fn size_key(size: f64) -> i64 {
+    (size * 2.0).round() as i64
+}
+
+/// Finds grids of crossing horizontal and vertical rules and reads the
+/// glyphs inside each cell. Returns the tables as spans covering their area,
+/// and the glyphs outside any table.
+fn ruled_tables(page: &PageLayout) -> (Vec<Span>, Vec<Glyph>) {
+    // Group rules that touch into connected components.
+    let rules = &page.rules;
+    let mut component: Vec<usize> = (0..rules.len()).collect();
+    fn root(component: &mut [usize], mut i: usize) -> usize {
+        while component[i] != i {
+            component[i] = component[component[i]];
+            i = component[i];
+        }
+        i
+    }
+    for i in 0..rules.len() {
+        for j in i + 1..rules.len() {
+            if rules[i].crosses(&rules[j]) {
+                let (a, b) = (root(&mut component, i), root(&mut component, j));
+                component[a] = b;
+            }
+        }
+    }
+    let mut grids: HashMap<usize, Vec<Rule>> = HashMap::new();
+    for (i, rule) in rules.iter().enumerate() {
+        let key = root(&mut component, i);
+        grids.entry(key).or_default().push(*rule);
+    }
+
+    let mut tables = Vec::new();
+    let mut glyphs = page.glyphs.clone();
+    let mut grids: Vec<Vec<Rule>> = grids.into_values().collect();
+    grids.sort_by(|a, b| a[0].position.total_cmp(&b[0].position));
+    for grid in grids {
+        let boundaries = |horizontal: bool| {
+            let mut positions: Vec<f64> = grid
+                .iter()
+                .filter(|rule| rule.horizontal == horizontal)
+                .map(|rule| rule.position)
+                .collect();
+            positions.sort_by(f64::total_cmp);
+            positions.dedup_by(|a, b| (*a - *b).abs() < RULE_TOLERANCE);
+            positions
+        };
+        let (row_edges, column_edges) = (boundaries(true), boundaries(false));
+        if row_edges.len() < 3 || column_edges.len() < 3 {
+            continue;
+        }
+
+        let cell_index = |edges: &[f64], position: f64| {
+            edges
+                .windows(2)
+                .position(|edge| position >= edge[0] && position < edge[1])
+        };
+        let mut cells: Vec<Vec<Vec<Glyph>>> =
+            vec![vec![Vec::new(); column_edges.len() - 1]; row_edges.len() - 1];
+        let mut inside = vec![false; glyphs.len()];
+        for (glyph, inside) in glyphs.iter().zip(&mut inside) {
+            let center_x = glyph.x + glyph.advance / 2.0;
+            let center_y = glyph.y - glyph.size * 0.3;
+            if let (Some(row), Some(column)) = (
+                cell_index(&row_edges, center_y),
+                cell_index(&column_edges, center_x),
+            ) {
+                cells[row][column].push(glyph.clone());
+                *inside = true;
+            }
+        }
+
+        let mut rows: Vec<Vec<String>> = cells
+            .iter()
+            .map(|row| {
+                row.iter()
+                    .map(|cell| {
+                        let mut spans = spans(cell);
+                        spans.sort_by(|a, b| a.y.total_cmp(&b.y).then(a.x0.total_cmp(&b.x0)));
+                        let lines = lines(spans, false);
+                        let mut text = String::new();
+                        for line in &lines {
+                            if text.is_empty() {
+                                text.push_str(&line.text);
+                            } else {
+                                join_line(&mut text, &line.text);
+                            }
+                        }
+                        text
+                    })
+                    .collect()
+            })
+            .collect();
+        rows.retain(|row| row.iter().any(|cell| !cell.is_empty()));
+        let used: Vec<usize> = (0..column_edges.len() - 1)
+            .filter(|&column| rows.iter().any(|row| !row[column].is_empty()))
+            .collect();
+        let rows: Vec<Vec<String>> = rows
+            .into_iter()
+            .map(|row| used.iter().map(|&column| row[column].clone()).collect())
+            .collect();
+        // Boxes around text and chart frames with tick marks are not tables.
+        let filled = rows
+            .iter()
+            .flatten()
+            .filter(|cell| !cell.is_empty())
+            .count();
+        if rows.len() < 2 || used.len() < 2 || filled * 2 < rows.len() * used.len() {
+            continue;
+        }
+        let mut inside = inside.into_iter();
+        glyphs.retain(|_| !inside.next().unwrap_or(false));
+
+        tables.push(Span {
+            x0: column_edges[0],
+            x1: column_edges[column_edges.len() - 1],
+            y: row_edges[0],
+            size: 0.0,
+            text: String::new(),
+            rotated: false,
+            table: Some(rows),
+        });
     }
+    (tables, glyphs)
 }
@@ -0,0 +1,462 @@
+//! Opening encrypted PDFs with the standard security handler: RC4 and
+//! AES-128 (revisions 2 to 4) and AES-256 (revisions 5 and 6), with either
+//! the user or the owner password.
+
+use aes::cipher::block_padding::{NoPadding, Pkcs7};
+use aes::cipher::{BlockDecryptMut, BlockEncryptMut, KeyIvInit};
+use lopdf::{Document, Object, ObjectId, ObjectStream, Reader};
+use md5::{Digest, Md5};
+use sha2::{Sha256, Sha384, Sha512};
+
+use crate::error::MarkitdownError;
+
+/// Appended to passwords shorter than 32 bytes before hashing.
+const PADDING: [u8; 32] = [
+    0x28, 0xbf, 0x4e, 0x5e, 0x4e, 0x75, 0x8a, 0x41, 0x64, 0x00, 0x4e, 0x56, 0xff, 0xfa, 0x01, 0x08,
+    0x2e, 0x2e, 0x00, 0xb6, 0xd0, 0x68, 0x3e, 0x80, 0x2f, 0x0c, 0xa9, 0xfe, 0x64, 0x53, 0x69, 0x7a,
+];
+
+/// Type given to object streams while they are still encrypted, so that
+/// lopdf does not try to parse them.
+const DEFERRED_OBJECT_STREAM: &[u8] = b"EncryptedObjStm";
+
+/// Loads a PDF and decrypts it when it is encrypted. Documents that only
+/// restrict permissions have an empty user password and open without one.
+pub(crate) fn load(bytes: &[u8], password: Option<&str>) -> Result<Document, MarkitdownError> {
+    let load_error =
+        |e: lopdf::Error| MarkitdownError::ParseError(format!("Failed to load PDF: {}", e));
+    let doc = Document::load_mem(bytes).map_err(load_error)?;
+    if !doc.is_encrypted() {
+        return Ok(doc);
+    }
+
+    // Object streams are encrypted as a whole, so the objects they hold can
+    // only be read once the stream is decrypted.
+    let mut doc = Reader {
+        buffer: bytes,
+        document: Document::new(),
+    }
+    .read(Some(defer_object_stream))
+    .map_err(load_error)?;
+    let handler = SecurityHandler::new(&doc)?;
+    let key = match password {
+        Some(password) => handler
+            .authenticate(password)
+            .or_else(|| handler.authenticate(""))
+            .ok_or_else(|| {
+                MarkitdownError::WrongPassword("The password does not open this PDF".to_string())
+            })?,
+        None => handler.authenticate("").ok_or_else(|| {
+            MarkitdownError::PasswordRequired("This PDF is encrypted".to_string())
+        })?,
+    };
+
+    for (&id, object) in doc.objects.iter_mut() {
+        if Some(id) == handler.dictionary_id {
+            continue;
+        }
+        if let Object::Stream(stream) = object {
+            // Cross-reference streams are never encrypted.
+            if stream.dict.type_is(b"XRef") {
+                continue;
+            }
+            for (_, value) in stream.dict.iter_mut() {
+                handler.decrypt_strings(&key, id, value);
+            }
+            if handler.encrypt_metadata || !stream.dict.type_is(b"Metadata") {
+                let content = handler.decrypt(handler.streams, &key, id, &stream.content);
+                stream.set_content(content);
+            }
+        } else {
+            handler.decrypt_strings(&key, id, object);
+        }
+    }
+
+    let mut contained = Vec::new();
+    for object in doc.objects.values_mut() {
+        if let Object::Stream(stream) = object {
+            if stream.dict.type_is(DEFERRED_OBJECT_STREAM) {
+                stream.dict.set("Type", Object::Name(b"ObjStm".to_vec()));
+                if let Ok(objects) = ObjectStream::new(stream) {
+                    contained.extend(objects.objects);
+                }
+            }
+        }
+    }
+    for (id, object) in contained {
+        doc.objects.entry(id).or_insert(object);
+    }
+    doc.trailer.remove(b"Encrypt");
+    Ok(doc)
+}
+
+/// Load filter that renames object streams. lopdf keeps the object it
+/// passed in and only looks at whether the filter returns `Some`.
+fn defer_object_stream(id: ObjectId, object: &mut Object) -> Option<(ObjectId, Object)> {
+    if let Object::Stream(stream) = object {
+        if stream.dict.type_is(b"ObjStm") {
+            stream
+                .dict
+                .set("Type", Object::Name(DEFERRED_OBJECT_STREAM.to_vec()));
+        }
+    }
+    Some((id, Object::Null))
+}
+
+/// How strings or streams are encrypted.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Method {
+    Identity,
+    Rc4,
+    Aes128,
+    Aes256,
+}
+
+/// The parameters of the `/Encrypt` dictionary.
+struct SecurityHandler {
+    dictionary_id: Option<ObjectId>,
+    revision: i64,
+    /// File key length in bytes.
+    key_length: usize,
+    owner: Vec<u8>,
+    user: Vec<u8>,
+    owner_key: Vec<u8>,
+    user_key: Vec<u8>,
+    permissions: i64,
+    file_id: Vec<u8>,
+    encrypt_metadata: bool,
+    strings: Method,
+    streams: Method,
+}
+
+impl SecurityHandler {
+    fn new(doc: &Document) -> Result<SecurityHandler, MarkitdownError> {
+        let invalid = |what: &str| {
+            MarkitdownError::ParseError(format!("Invalid PDF encryption dictionary: {}", what))
+        };
+        let reference = doc
+            .trailer
+            .get(b"Encrypt")
+            .map_err(|_| invalid("missing"))?;
+        let dictionary_id = reference.as_reference().ok();
+        let encrypt = doc
+            .dereference(reference)
+            .and_then(|(_, object)| object.as_dict())
+            .map_err(|_| invalid("not a dictionary"))?;
+
+        let name = |key: &[u8]| encrypt.get(key).and_then(Object::as_name).ok();
+        let integer = |key: &[u8]| encrypt.get(key).and_then(Object::as_i64).ok();
+        let string = |key: &[u8]| {
+            encrypt
+                .get(key)
+                .and_then(Object::as_str)
+                .map(<[u8]>::to_vec)
+                .unwrap_or_default()
+        };
+
+        let filter = name(b"Filter").unwrap_or_default();
+        if filter != b"Standard" {
+            return Err(MarkitdownError::UnsupportedEncryption(format!(
+                "Unsupported PDF security handler: {}",
+                String::from_utf8_lossy(filter)
+            )));
+        }
+        let version = integer(b"V").unwrap_or(0);
+        let revision = integer(b"R").ok_or_else(|| invalid("missing revision"))?;
+        if !(2..=6).contains(&revision) {
+            return Err(MarkitdownError::UnsupportedEncryption(format!(
+                "Unsupported PDF security handler revision {}",
+                revision
+            )));
+        }
+        let rc4_key_length = (integer(b"Length").unwrap_or(40) / 8).clamp(5, 16) as usize;
+
+        // Version 4 and later name a crypt filter for strings and streams.
+        let crypt_filter = |key: &[u8]| -> Method {
+            let Some(filter) = name(key) else {
+                return Method::Identity;
+            };
+            let method = encrypt
+                .get(b"CF")
+                .and_then(|filters| doc.dereference(filters))
+                .and_then(|(_, filters)| filters.as_dict())
+                .and_then(|filters| filters.get(filter))
+                .and_then(|filter| doc.dereference(filter))
+                .and_then(|(_, filter)| filter.as_dict())
+                .and_then(|filter| filter.get(b"CFM"))
+                .and_then(Object::as_name);
+            match method {
+                Ok(b"V2") => Method::Rc4,
+                Ok(b"AESV2") => Method::Aes128,
+                Ok(b"AESV3") => Method::Aes256,
+                _ => Method::Identity,
+            }
+        };
+        let (strings, streams, key_length) = match version {
+            1 => (Method::Rc4, Method::Rc4, 5),
+            2 => (Method::Rc4, Method::Rc4, rc4_key_length),
+            4 | 5 => {
+                let (strings, streams) = (crypt_filter(b"StrF"), crypt_filter(b"StmF"));
+                let key_length = if version == 5 {
+                    32
+                } else if strings == Method::Aes128 || streams == Method::Aes128 {
+                    16
+                } else {
+                    rc4_key_length
+                };
+                (strings, streams, key_length)
+            }
+            _ => {
+                return Err(MarkitdownError::UnsupportedEncryption(format!(
+                    "Unsupported PDF encryption version {}",
+                    version
+                )))
+            }
+        };
+
+        let file_id = doc
+            .trailer
+            .get(b"ID")
+            .and_then(Object::as_array)
+            .ok()
+            .and_then(|ids| ids.first())
+            .and_then(|id| id.as_str().ok())
+            .map(<[u8]>::to_vec)
+            .unwrap_or_default();
+
+        Ok(SecurityHandler {
+            dictionary_id,
+            revision,
+            key_length,
+            owner: string(b"O"),
+            user: string(b"U"),
+            owner_key: string(b"OE"),
+            user_key: string(b"UE"),
+            permissions: integer(b"P").ok_or_else(|| invalid("missing permissions"))?,
+            file_id,
+            encrypt_metadata: encrypt
+                .get(b"EncryptMetadata")
+                .and_then(Object::as_bool)
+                .unwrap_or(true),
+            strings,
+            streams,
+        })
+    }
+
+    /// Returns the file key when `password` is the user or the owner
+    /// password.
+    fn authenticate(&self, password: &str) -> Option<Vec<u8>> {
+        if self.revision >= 5 {
+            let password = &password.as_bytes()[..password.len().min(127)];
+            if self.owner.len() < 48 || self.user.len() < 48 {
+                return None;
+            }
+            let user = &self.user[..48];
+            if self.hash(password, &self.owner[32..40], user) == self.owner[..32] {
+                let key = self.hash(password, &self.owner[40..48], user);
+                return aes256_unwrap(&key, &self.owner_key);
+            }
+            if self.hash(password, &self.user[32..40], &[]) == self.user[..32] {
+                let key = self.hash(password, &self.user[40..48], &[]);
+                return aes256_unwrap(&key, &self.user_key);
+            }
+            return None;
+        }
+
+        // Older revisions take passwords in PDFDocEncoding, which matches
+        // Latin-1 for the characters people type.
+        let password: Vec<u8> = password
+            .chars()
+            .map(|c| u8::try_from(c).unwrap_or(b'?'))
+            .collect();
+        self.check_user_password(&password).or_else(|| {
+            let user_password = self.user_password_from_owner(&password);
+            self.check_user_password(&user_password)
+        })
+    }
+
+    /// Algorithms 2, 4 and 5: derives the file key from a user password and
+    /// checks it against `/U`.
+    fn check_user_password(&self, password: &[u8]) -> Option<Vec<u8>> {
+        let mut hasher = Md5::new();
+        hasher.update(padded(password));
+        hasher.update(&self.owner[..self.owner.len().min(32)]);
+        hasher.update((self.permissions as u32).to_le_bytes());
+        hasher.update(&self.file_id);
+        if self.revision >= 4 && !self.encrypt_metadata {
+            hasher.update([0xff; 4]);
+        }
+        let mut digest = hasher.finalize().to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = Md5::digest(&digest[..self.key_length]).to_vec();
+            }
+        }
+        let key = digest[..self.key_length].to_vec();
+
+        let matches = if self.revision == 2 {
+            rc4(&key, &PADDING) == self.user
+        } else {
+            let mut hasher = Md5::new();
+            hasher.update(PADDING);
+            hasher.update(&self.file_id);
+            let check = xor_rounds(&key, hasher.finalize().to_vec(), 0..=19);
+            self.user.len() >= 16 && check == self.user[..16]
+        };
+        matches.then_some(key)
+    }
+
+    /// Algorithm 7: recovers the padded user password from `/O` with the
+    /// owner password.
+    fn user_password_from_owner(&self, password: &[u8]) -> Vec<u8> {
+        let mut digest = Md5::digest(padded(password)).to_vec();
+        if self.revision >= 3 {
+            for _ in 0..50 {
+                digest = Md5::digest(&digest).to_vec();
+            }
+        }
+        let key = &digest[..self.key_length];
+        if self.revision == 2 {
+            rc4(key, &self.owner)
+        } else {
+            xor_rounds(key, self.owner.clone(), (0..=19).rev())
+        }
+    }
+
+    /// Algorithm 2.B: the password hash of revision 6, or plain SHA-256 for
+    /// revision 5.
+    fn hash(&self, password: &[u8], salt: &[u8], user: &[u8]) -> Vec<u8> {
+        let mut hash = Sha256::new()
+            .chain_update(password)
+            .chain_update(salt)
+            .chain_update(user)
+            .finalize()
+            .to_vec();
+        if self.revision < 6 {
+            return hash;
+        }
+        let mut round = 0;
+        loop {
+            let block = [password, &hash, user].concat().repeat(64);
+            let encrypted =
+                cbc::Encryptor::<aes::Aes128>::new_from_slices(&hash[..16], &hash[16..32])
+                    .expect("hash is at least 32 bytes")
+                    .encrypt_padded_vec_mut::<NoPadding>(&block);
+            // The first 16 bytes as a big-endian number, modulo 3.
+            let remainder = encrypted[..16].iter().map(|&b| b as u32).sum::<u32>() % 3;
+            hash = match remainder {
+                0 => Sha256::digest(&encrypted).to_vec(),
+                1 => Sha384::digest(&encrypted).to_vec(),
+                _ => Sha512::digest(&encrypted).to_vec(),
+            };
+            round += 1;
+            let last = *encrypted.last().expect("block is not empty") as u32;
+            if round >= 64 && last + 32 <= round {
+                break;
+            }
+        }
+        hash.truncate(32);
+        hash
+    }
+
+    fn decrypt_strings(&self, key: &[u8], id: ObjectId, object: &mut Object) {
+        match object {
+            Object::String(content, _) => *content = self.decrypt(self.strings, key, id, content),
+            Object::Array(items) => {
+                for item in items {
+                    self.decrypt_strings(key, id, item);
+                }
+            }
+            Object::Dictionary(dictionary) => {
+                for (_, value) in dictionary.iter_mut() {
+                    self.decrypt_strings(key, id, value);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    /// Algorithm 1: decrypts a string or stream of object `id`.
+    fn decrypt(&self, method: Method, key: &[u8], id: ObjectId, data: &[u8]) -> Vec<u8> {
+        if method == Method::Identity {
+            return data.to_vec();
+        }
+        if method == Method::Aes256 {
+            return aes_decrypt(key, data);
+        }
+        let mut hasher = Md5::new();
+        hasher.update(key);
+        hasher.update(&id.0.to_le_bytes()[..3]);
+        hasher.update(&id.1.to_le_bytes()[..2]);
+        if method == Method::Aes128 {
+            hasher.update(b"sAlT");
+        }
+        let object_key = hasher.finalize();
+        match method {
+            Method::Aes128 => aes_decrypt(&object_key, data),
+            _ => rc4(&object_key[..(key.len() + 5).min(16)], data),
+        }
+    }
+}
+
+fn padded(password: &[u8]) -> Vec<u8> {
+    let password = &password[..password.len().min(32)];
+    [password, &PADDING[..32 - password.len()]].concat()
+}
+
+/// Encrypts `data` with RC4 once for each round, using the key XORed with
+/// the round number.
+fn xor_rounds(key: &[u8], mut data: Vec<u8>, rounds: impl Iterator<Item = u8>) -> Vec<u8> {
+    for round in rounds {
+        let round_key: Vec<u8> = key.iter().map(|b| b ^ round).collect();
+        data = rc4(&round_key, &data);
+    }
+    data
+}
+
+fn rc4(key: &[u8], data: &[u8]) -> Vec<u8> {
+    let mut state: [u8; 256] = std::array::from_fn(|i| i as u8);
+    let mut j = 0u8;
+    for i in 0..256 {
+        j = j.wrapping_add(state[i]).wrapping_add(key[i % key.len()]);
+        state.swap(i, j as usize);
+    }
+    let (mut i, mut j) = (0u8, 0u8);
+    data.iter()
+        .map(|&byte| {
+            i = i.wrapping_add(1);
+            j = j.wrapping_add(state[i as usize]);
+            state.swap(i as usize, j as usize);
+            byte ^ state[state[i as usize].wrapping_add(state[j as usize]) as usize]
+        })
+        .collect()
+}
+
+/// Decrypts AES-CBC data that starts with its initialisation vector.
+fn aes_decrypt(key: &[u8], data: &[u8]) -> Vec<u8> {
+    if data.len() < 32 || data.len() % 16 != 0 {
+        return Vec::new();
+    }
+    let (iv, data) = data.split_at(16);
+    let plain = if key.len() == 32 {
+        cbc::Decryptor::<aes::Aes256>::new_from_slices(key, iv)
+            .ok()
+            .and_then(|cipher| cipher.decrypt_padded_vec_mut::<Pkcs7>(data).ok())
+    } else {
+        cbc::Decryptor::<aes::Aes128>::new_from_slices(key, iv)
+            .ok()
+            .and_then(|cipher| cipher.decrypt_padded_vec_mut::<Pkcs7>(data).ok())
+    };
+    plain.unwrap_or_default()
+}
+
+/// Decrypts the file key stored in `/OE` or `/UE`.
+fn aes256_unwrap(key: &[u8], wrapped: &[u8]) -> Option<Vec<u8>> {
+    if wrapped.len() != 32 {
+        return None;
+    }
+    cbc::Decryptor::<aes::Aes256>::new_from_slices(key, &[0; 16])
+        .ok()?
+        .decrypt_padded_vec_mut::<NoPadding>(wrapped)
+        .ok()
+}
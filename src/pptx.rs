@@ -169,6 +169,7 @@ impl DocumentConverter for PptxConverter {
         Ok(DocumentConverterResult {
             title: None,
             text_content: markdown,
+            ..Default::default()
         })
     }
 
@@ -335,6 +336,7 @@ impl DocumentConverter for PptxConverter {
         Ok(DocumentConverterResult {
             title: None,
             text_content: markdown,
+            ..Default::default()
         })
     }
 }
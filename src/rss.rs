@@ -1,7 +1,7 @@
 use crate::error::MarkitdownError;
+use crate::html::html_to_markdown;
 use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
 use feed_rs::parser;
-use html2md::parse_html;
 use std::fs::File;
 use std::io::BufReader;
 
@@ -30,14 +30,15 @@ impl DocumentConverter for RssConverter {
         let mut markdown = String::new();
 
         if feed.feed_type == feed_rs::model::FeedType::Atom {
-            markdown = parse_atom_type(feed);
+            markdown = parse_atom_type(feed, &args);
         } else if feed.feed_type == feed_rs::model::FeedType::RSS2 {
-            markdown = parse_rss_type(feed);
+            markdown = parse_rss_type(feed, &args);
         }
 
         Ok(DocumentConverterResult {
             title: None,
             text_content: markdown,
+            ..Default::default()
         })
     }
 
@@ -62,19 +63,20 @@ impl DocumentConverter for RssConverter {
         let mut markdown = String::new();
 
         if feed.feed_type == feed_rs::model::FeedType::Atom {
-            markdown = parse_atom_type(feed);
+            markdown = parse_atom_type(feed, &args);
         } else if feed.feed_type == feed_rs::model::FeedType::RSS2 {
-            markdown = parse_rss_type(feed);
+            markdown = parse_rss_type(feed, &args);
         }
 
         Ok(DocumentConverterResult {
             title: None,
             text_content: markdown,
+            ..Default::default()
         })
     }
 }
 
-fn parse_atom_type(feed: feed_rs::model::Feed) -> String {
+fn parse_atom_type(feed: feed_rs::model::Feed, args: &Option<ConversionOptions>) -> String {
     let mut markdown = String::new();
     if let Some(title) = &feed.title {
         markdown.push_str(&format!("# {}\n", title.content));
@@ -91,7 +93,7 @@ fn parse_atom_type(feed: feed_rs::model::Feed) -> String {
 
         if let Some(content) = &entry.content {
             if let Some(body) = &content.body {
-                markdown.push_str(&parse_html(body));
+                markdown.push_str(&html_to_markdown(body, &entry_options(&feed, entry, args)));
                 markdown.push_str("\n");
             }
         }
@@ -99,7 +101,7 @@ fn parse_atom_type(feed: feed_rs::model::Feed) -> String {
     markdown
 }
 
-fn parse_rss_type(feed: feed_rs::model::Feed) -> String {
+fn parse_rss_type(feed: feed_rs::model::Feed, args: &Option<ConversionOptions>) -> String {
     let mut markdown = String::new();
     if let Some(title) = &feed.title {
         markdown.push_str(&format!("# {}\n", title.content));
@@ -120,13 +122,37 @@ fn parse_rss_type(feed: feed_rs::model::Feed) -> String {
         }
 
         if let Some(summary) = &entry.summary {
-            markdown.push_str(&parse_html(&summary.content));
+            markdown.push_str(&html_to_markdown(
+                &summary.content,
+                &entry_options(&feed, entry, args),
+            ));
             markdown.push_str("\n");
         }
     });
     markdown
 }
 
+/// Options for rendering the HTML of an entry, whose relative links resolve
+/// against the feed URL when known, else the entry's or the feed's own link.
+fn entry_options(
+    feed: &feed_rs::model::Feed,
+    entry: &feed_rs::model::Entry,
+    args: &Option<ConversionOptions>,
+) -> Option<ConversionOptions> {
+    let mut options = args.clone().unwrap_or_default();
+    // Entries are fragments; there is no page chrome to strip.
+    options.html_main_content = false;
+    if options.url.is_none() {
+        options.url = entry
+            .links
+            .iter()
+            .chain(feed.links.iter())
+            .map(|link| link.href.clone())
+            .next();
+    }
+    Some(options)
+}
+
 // fn parse_rss_use_rss_crate(local_path: &str) -> String {
 //     let file = File::open(local_path).unwrap();
 //     let channel = Channel::read_from(BufReader::new(file)).unwrap();
@@ -0,0 +1,123 @@
+use markup5ever_rcdom::Handle;
+use url::Url;
+
+use crate::error::MarkitdownError;
+use crate::html::{
+    attr, canonical_url, collapse_whitespace, convert_site_bytes, convert_site_file, element_name,
+    find_element, find_elements, has_class, has_id, meta_content, render_node, text_content, Site,
+    SitePage,
+};
+use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+
+/// Converts saved Stack Overflow and other Stack Exchange question pages
+/// into the question followed by its answers, without votes, comments and
+/// site navigation.
+pub struct StackExchangeConverter;
+
+/// Network sites that do not live under `stackexchange.com`.
+const SITE_HOSTS: [&str; 7] = [
+    "stackoverflow.com",
+    "stackexchange.com",
+    "superuser.com",
+    "serverfault.com",
+    "askubuntu.com",
+    "mathoverflow.net",
+    "stackapps.com",
+];
+
+pub(crate) const SITE: Site = Site {
+    page: "Stack Exchange question",
+    detect: is_stackexchange_page,
+    render: render_question,
+};
+
+impl DocumentConverter for StackExchangeConverter {
+    fn convert(
+        &self,
+        local_path: &str,
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        convert_site_file(&SITE, local_path, &args)
+    }
+
+    fn convert_bytes(
+        &self,
+        bytes: &[u8],
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        convert_site_bytes(&SITE, bytes, &args)
+    }
+}
+
+/// The question with its tags, followed by the answers and their scores.
+fn render_question(
+    document: &Handle,
+    args: &Option<ConversionOptions>,
+) -> Result<SitePage, MarkitdownError> {
+    let question = find_element(document, &|node| has_id(node, "question"))
+        .ok_or_else(|| MarkitdownError::ParseError("Missing question".to_string()))?;
+
+    let title = find_element(document, &|node| has_id(node, "question-header"))
+        .and_then(|header| find_element(&header, &|node| element_name(node) == Some("h1")))
+        .map(|heading| collapse_whitespace(&text_content(&heading)))
+        .or_else(|| meta_content(document, "og:title"))
+        .filter(|title| !title.is_empty());
+
+    let mut markdown = String::new();
+    if let Some(title) = &title {
+        markdown.push_str(&format!("# {}\n\n", title));
+    }
+    if let Some(body) = post_body(&question) {
+        markdown.push_str(&render_node(&body, document, args));
+        markdown.push_str("\n\n");
+    }
+    let tags: Vec<String> = find_elements(&question, &|node| has_class(node, "post-tag"))
+        .iter()
+        .map(|tag| collapse_whitespace(&text_content(tag)))
+        .collect();
+    if !tags.is_empty() {
+        markdown.push_str(&format!("Tags: {}\n\n", tags.join(", ")));
+    }
+
+    for answer in find_elements(document, &|node| has_class(node, "answer")) {
+        let Some(body) = post_body(&answer) else {
+            continue;
+        };
+        let mut heading = String::from("## ");
+        heading.push_str(if has_class(&answer, "accepted-answer") {
+            "Accepted answer"
+        } else {
+            "Answer"
+        });
+        if let Some(score) = attr(&answer, "data-score") {
+            heading.push_str(&format!(" (score {})", score));
+        }
+        markdown.push_str(&format!("{}\n\n", heading));
+        markdown.push_str(&render_node(&body, document, args));
+        markdown.push_str("\n\n");
+    }
+
+    Ok((title, markdown))
+}
+
+fn is_stackexchange_page(document: &Handle, page_url: Option<&str>) -> bool {
+    let on_network = [page_url.map(str::to_string), canonical_url(document)]
+        .into_iter()
+        .flatten()
+        .filter_map(|url| Url::parse(&url).ok())
+        .any(|url| {
+            url.host_str().is_some_and(|host| {
+                SITE_HOSTS
+                    .iter()
+                    .any(|site| host == *site || host.ends_with(&format!(".{}", site)))
+            })
+        });
+    on_network && find_element(document, &|node| has_id(node, "question")).is_some()
+}
+
+/// The rendered post text, without its vote buttons, tags, signature and
+/// comments.
+fn post_body(post: &Handle) -> Option<Handle> {
+    find_element(post, &|node| has_class(node, "js-post-body"))
+        .or_else(|| find_element(post, &|node| has_class(node, "post-text")))
+}
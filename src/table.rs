@@ -0,0 +1,39 @@
+//! Rendering of table rows as GFM tables, shared by the converters that
+//! produce them.
+
+/// Renders rows as a GFM table whose first row is the header.
+pub(crate) fn rows_to_markdown(rows: &[Vec<String>]) -> String {
+    let mut markdown = String::new();
+    let Some(header) = rows.first() else {
+        return markdown;
+    };
+
+    markdown.push('|');
+    for cell in header {
+        markdown.push_str(&format!(" {} |", escape_cell(cell)));
+    }
+    markdown.push_str("\n|");
+
+    for _ in header {
+        markdown.push_str(" --- |");
+    }
+    markdown.push('\n');
+
+    for row in rows.iter().skip(1) {
+        markdown.push('|');
+        for cell in row {
+            markdown.push_str(&format!(" {} |", escape_cell(cell)));
+        }
+        markdown.push('\n');
+    }
+
+    markdown
+}
+
+/// Escapes pipes and turns line breaks into `<br>`, which would otherwise
+/// end the cell or the row.
+pub(crate) fn escape_cell(cell: &str) -> String {
+    cell.replace('|', "\\|")
+        .replace("\r\n", "<br>")
+        .replace(['\n', '\r'], "<br>")
+}
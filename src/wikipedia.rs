@@ -0,0 +1,181 @@
+use markup5ever_rcdom::Handle;
+use url::Url;
+
+use crate::error::MarkitdownError;
+use crate::html::{
+    attr, canonical_url, collapse_whitespace, convert_site_bytes, convert_site_file, element_name,
+    find_element, find_elements, has_class, has_id, remove_elements, render_node, text_content,
+    Site, SitePage,
+};
+use crate::model::{ConversionOptions, DocumentConverter, DocumentConverterResult};
+
+/// Converts saved Wikipedia articles, keeping only the article body and
+/// summarising the infobox. Other pages are left to `HtmlConverter`.
+pub struct WikipediaConverter;
+
+/// Classes of elements that are editing aids, citations or navigation
+/// rather than article text.
+const NOISE_CLASSES: [&str; 16] = [
+    "mw-editsection",
+    "reference",
+    "reflist",
+    "references",
+    "mw-references-wrap",
+    "navbox",
+    "navbox-styles",
+    "vertical-navbox",
+    "hatnote",
+    "toc",
+    "mw-empty-elt",
+    "noprint",
+    "metadata",
+    "ambox",
+    "sistersitebox",
+    "catlinks",
+];
+
+/// Section ids whose content is citations only.
+const REFERENCE_SECTIONS: [&str; 5] = ["References", "Notes", "Citations", "Sources", "Footnotes"];
+
+pub(crate) const SITE: Site = Site {
+    page: "Wikipedia article",
+    detect: is_wikipedia_page,
+    render: render_article,
+};
+
+impl DocumentConverter for WikipediaConverter {
+    fn convert(
+        &self,
+        local_path: &str,
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        convert_site_file(&SITE, local_path, &args)
+    }
+
+    fn convert_bytes(
+        &self,
+        bytes: &[u8],
+        args: Option<ConversionOptions>,
+    ) -> Result<DocumentConverterResult, MarkitdownError> {
+        convert_site_bytes(&SITE, bytes, &args)
+    }
+}
+
+/// The article title and body, starting with a summary of its infoboxes.
+fn render_article(
+    document: &Handle,
+    args: &Option<ConversionOptions>,
+) -> Result<SitePage, MarkitdownError> {
+    let body = find_element(document, &|node| has_id(node, "mw-content-text"))
+        .ok_or_else(|| MarkitdownError::ParseError("Missing article content".to_string()))?;
+
+    let title = find_element(document, &|node| has_class(node, "mw-page-title-main"))
+        .or_else(|| find_element(document, &|node| has_id(node, "firstHeading")))
+        .map(|node| collapse_whitespace(&text_content(&node)))
+        .filter(|title| !title.is_empty());
+
+    remove_reference_sections(&body);
+    remove_elements(&body, &|node| {
+        NOISE_CLASSES.iter().any(|class| has_class(node, class))
+    });
+
+    let mut markdown = String::new();
+    if let Some(title) = &title {
+        markdown.push_str(&format!("# {}\n\n", title));
+    }
+    for infobox in find_elements(&body, &|node| has_class(node, "infobox")) {
+        let summary = infobox_to_markdown(&infobox, document, args);
+        if !summary.is_empty() {
+            markdown.push_str(&summary);
+            markdown.push_str("\n\n");
+        }
+    }
+    remove_elements(&body, &|node| has_class(node, "infobox"));
+    markdown.push_str(&render_node(&body, document, args));
+
+    Ok((title, markdown))
+}
+
+/// Recognises Wikipedia by the page URL, falling back to the canonical link
+/// saved with the page.
+fn is_wikipedia_page(document: &Handle, page_url: Option<&str>) -> bool {
+    let on_wikipedia = [page_url.map(str::to_string), canonical_url(document)]
+        .into_iter()
+        .flatten()
+        .filter_map(|url| Url::parse(&url).ok())
+        .any(|url| {
+            url.host_str()
+                .is_some_and(|host| host == "wikipedia.org" || host.ends_with(".wikipedia.org"))
+        });
+    on_wikipedia && find_element(document, &|node| has_id(node, "mw-content-text")).is_some()
+}
+
+/// Renders the label/value rows of an infobox as a list. Images, headers
+/// and spanning rows are left out.
+fn infobox_to_markdown(
+    infobox: &Handle,
+    document: &Handle,
+    args: &Option<ConversionOptions>,
+) -> String {
+    let mut items = Vec::new();
+    for row in find_elements(infobox, &|node| element_name(node) == Some("tr")) {
+        let cells: Vec<Handle> = row
+            .children
+            .borrow()
+            .iter()
+            .filter(|cell| matches!(element_name(cell), Some("th" | "td")))
+            .cloned()
+            .collect();
+        let [label, value] = cells.as_slice() else {
+            continue;
+        };
+        let label = collapse_whitespace(&text_content(label));
+        let value = render_node(value, document, args)
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty())
+            .collect::<Vec<_>>()
+            .join(" ");
+        if !label.is_empty() && !value.is_empty() {
+            items.push(format!("- **{}**: {}", label, value));
+        }
+    }
+    items.join("\n")
+}
+
+/// Drops reference sections: the heading and everything up to the next
+/// heading of the same or a higher level.
+fn remove_reference_sections(body: &Handle) {
+    let Some(output) = find_element(body, &|node| has_class(node, "mw-parser-output")) else {
+        return;
+    };
+    let mut skipping: Option<usize> = None;
+    output.children.borrow_mut().retain(|child| {
+        if let Some((level, id)) = section_heading(child) {
+            if skipping.is_some_and(|skip_level| level > skip_level) {
+                return false;
+            }
+            skipping = REFERENCE_SECTIONS.contains(&id.as_str()).then_some(level);
+        }
+        skipping.is_none()
+    });
+}
+
+/// Level and id of a section heading, either a bare `<h2>` or the
+/// `<div class="mw-heading">` wrapper newer MediaWiki versions emit.
+fn section_heading(node: &Handle) -> Option<(usize, String)> {
+    let heading = if has_class(node, "mw-heading") {
+        find_element(node, &|child| {
+            matches!(element_name(child), Some("h2" | "h3" | "h4" | "h5" | "h6"))
+        })?
+    } else {
+        node.clone()
+    };
+    let name = element_name(&heading)?;
+    let level = name.strip_prefix('h')?.parse().ok()?;
+    let id = attr(&heading, "id").or_else(|| {
+        find_element(&heading, &|child| has_class(child, "mw-headline"))
+            .and_then(|headline| attr(&headline, "id"))
+    })?;
+    Some((level, id))
+}
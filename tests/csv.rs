@@ -1,4 +1,8 @@
-use markitdown::{model::ConversionOptions, MarkItDown};
+use markitdown::{
+    error::MarkitdownError,
+    model::{ConversionOptions, WarningKind},
+    MarkItDown,
+};
 
 #[test]
 fn test_plaintext_conversion() {
@@ -7,6 +11,7 @@ fn test_plaintext_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -23,6 +28,7 @@ fn test_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -31,3 +37,183 @@ fn test_bytes_conversion() {
     assert!(result.is_ok());
     assert!(result.unwrap().is_some());
 }
+
+#[test]
+fn test_csv_markdown_table() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.csv", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert_eq!(
+        text,
+        "| 名前 | 年齢 | 住所 |\n\
+         | --- | ---: | --- |\n\
+         | 佐藤太郎 | 30 | 東京 |\n\
+         | 三木英子 | 25 | 大阪 |\n\
+         | 髙橋淳 | 35 | 名古屋 |\n"
+    );
+}
+
+#[test]
+fn test_csv_escaping_and_generated_header() {
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let csv = b"1,\"a|b\",\"two\nlines\"\n2,\"c, d\",plain\n";
+    let result = markitdown.convert_bytes(csv, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.starts_with("| Column 1 | Column 2 | Column 3 |\n| ---: | --- | --- |\n"));
+    assert!(text.contains("| 1 | a\\|b | two<br>lines |"));
+    assert!(text.contains("| 2 | c, d | plain |"));
+}
+
+#[test]
+fn test_csv_row_cap() {
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        csv_max_rows: Some(2),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let csv = b"name,score\na,1\nb,2\nc,3\nd,4\n";
+    let result = markitdown.convert_bytes(csv, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| b | 2 |\n\n… 2 more rows"));
+    assert!(!text.contains("| c | 3 |"));
+}
+
+#[test]
+fn test_csv_row_cap_without_header() {
+    let markitdown = MarkItDown::new();
+    let csv = b"1,2\n3,4\n5,6\n7,8\n";
+
+    for has_header in [Some(false), None] {
+        let options = ConversionOptions {
+            file_extension: Some(".csv".to_string()),
+            csv_max_rows: Some(2),
+            csv_has_header: has_header,
+            ..Default::default()
+        };
+        let result = markitdown.convert_bytes(csv, Some(options));
+        let text = result.unwrap().unwrap().text_content;
+        assert!(text.contains("| 3 | 4 |\n\n… 2 more rows"));
+        assert!(!text.contains("| 5 | 6 |"));
+    }
+}
+
+#[test]
+fn test_csv_semicolon_dialect() {
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let csv = "Produkt;Preis;Menge\n\"Käse; alt\";3,50;2\nBrot;2,10;1\n".as_bytes();
+    let result = markitdown.convert_bytes(csv, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.starts_with("| Produkt | Preis | Menge |\n"));
+    assert!(text.contains("| Käse; alt | 3,50 | 2 |"));
+}
+
+#[test]
+fn test_tsv_conversion() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.tsv", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.starts_with("| id | name | city |\n| ---: | --- | --- |\n"));
+    assert!(text.contains("| 2 | Bob | Berlin, DE |"));
+}
+
+#[test]
+fn test_csv_pipe_and_escape_detection() {
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let csv = b"key|value\na|\"say \\\"hi\\\"\"\nb|plain\n";
+    let result = markitdown.convert_bytes(csv, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| a | say \"hi\" |"));
+}
+
+#[test]
+fn test_csv_dialect_overrides() {
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        csv_delimiter: Some(b':'),
+        csv_quote: Some(b'\''),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let csv = b"host:port\n'db:primary':5432\n";
+    let result = markitdown.convert_bytes(csv, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| db:primary | 5432 |"));
+}
+
+#[test]
+fn test_csv_malformed_rows_reported_as_warnings() {
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let csv = b"name,score\na,1\nb,2,extra\nc\xff,3\n";
+    let result = markitdown.convert_bytes(csv, Some(options)).unwrap().unwrap();
+    assert!(result.text_content.contains("| b | 2 | extra |"));
+    assert!(!result.text_content.contains("Error"));
+
+    let warnings = &result.warnings;
+    assert_eq!(warnings.len(), 2);
+    assert_eq!(warnings[0].line, Some(3));
+    assert_eq!(warnings[0].kind, WarningKind::UnequalLengths);
+    assert_eq!(warnings[0].message, "expected 2 fields, found 3");
+    assert_eq!(warnings[1].line, Some(4));
+    assert_eq!(warnings[1].kind, WarningKind::InvalidUtf8);
+}
+
+#[test]
+fn test_csv_strict_mode() {
+    let malformed = b"name,score\na,1\nb,2,extra\n";
+    let markitdown = MarkItDown::new();
+    let options = ConversionOptions {
+        file_extension: Some(".csv".to_string()),
+        csv_strict: true,
+        ..Default::default()
+    };
+
+    let result = markitdown.convert_bytes(malformed, Some(options.clone()));
+    assert!(matches!(result, Err(MarkitdownError::Malformed(_))));
+
+    let dir = tempfile::tempdir().unwrap();
+    let path = dir.path().join("malformed.csv");
+    std::fs::write(&path, malformed).unwrap();
+    let result = markitdown.convert(path.to_str().unwrap(), Some(options));
+    assert!(matches!(result, Err(MarkitdownError::Malformed(_))));
+
+    // Without strict mode the record is reported and the rest converted.
+    let result = markitdown.convert_bytes(
+        malformed,
+        Some(ConversionOptions {
+            file_extension: Some(".csv".to_string()),
+            ..Default::default()
+        }),
+    );
+    assert_eq!(result.unwrap().unwrap().warnings.len(), 1);
+}
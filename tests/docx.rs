@@ -7,6 +7,7 @@ fn test_docx_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -25,6 +26,7 @@ fn test_docx_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -36,6 +38,8 @@ fn test_docx_bytes_conversion() {
 
 fn write_to_file(content: &str) {
     use std::io::Write;
-    let mut file = std::fs::File::create("test.md").unwrap();
+    // Kept out of the working tree so test runs leave nothing to commit.
+    let path = std::env::temp_dir().join("markitdown-test.md");
+    let mut file = std::fs::File::create(path).unwrap();
     file.write_all(content.as_bytes()).unwrap();
 }
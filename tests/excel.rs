@@ -1,4 +1,8 @@
-use markitdown::{model::ConversionOptions, MarkItDown};
+use markitdown::{
+    error::MarkitdownError,
+    model::{ConversionOptions, MergedCellMode},
+    MarkItDown,
+};
 
 #[test]
 fn test_excel_conversion() {
@@ -7,6 +11,7 @@ fn test_excel_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -24,6 +29,7 @@ fn test_excel_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -33,8 +39,165 @@ fn test_excel_bytes_conversion() {
     assert!(result.unwrap().is_some());
 }
 
+#[test]
+fn test_xls_conversion() {
+    let options = ConversionOptions {
+        file_extension: Some(".xls".to_string()),
+        url: None,
+        llm_client: None,
+        llm_model: None,
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.xls", Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| Alpha | Beta | Gamma |"));
+    assert!(text.contains("| 4 | 5.5 | 6 |"));
+}
+
+#[test]
+fn test_ods_bytes_conversion() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert_bytes(include_bytes!("./test_files/test.ods"), None);
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| Alpha | Beta | Gamma |"));
+    assert!(text.contains("| 1 | 2 | 3 |"));
+}
+
+#[test]
+fn test_xls_bytes_detected_as_xlsx() {
+    // A mismatched extension must still fall back to content sniffing.
+    let options = ConversionOptions {
+        file_extension: Some(".xlsx".to_string()),
+        url: None,
+        llm_client: None,
+        llm_model: None,
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert_bytes(include_bytes!("./test_files/test.xls"), Some(options));
+    assert!(result.unwrap().unwrap().text_content.contains("Alpha"));
+}
+
+#[test]
+fn test_excel_number_formats() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_formats.xlsx", None);
+    let text = result.unwrap().unwrap().text_content;
+    let lines: Vec<&str> = text.lines().skip(2).collect();
+    // Empty formatted cells around the data are trimmed away.
+    assert_eq!(lines[0], "| Item | Date | Share | Revenue | Time | Note |");
+    assert_eq!(
+        lines[2],
+        "| Widget | 2023-07-15 | 12.5% | 1,234,567.89 | 6:00 PM | a\\|b |"
+    );
+    assert_eq!(
+        lines[3],
+        "| Gadget | 2 Jan 2024 | 50.0% | ($1,500) | 7:30 AM | line1<br>line2 |"
+    );
+    assert_eq!(lines.len(), 4);
+}
+
+#[test]
+fn test_excel_merged_cells_and_comments() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_merged.xlsx", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| Region | Revenue | Revenue |"));
+    assert!(text.contains("| Region | Q1 | Q2 |"));
+    assert!(text.contains("| North | 10 [^B3] | 20 |"));
+    assert!(text.contains("[^B3]: Author: Checked by finance"));
+}
+
+#[test]
+fn test_excel_formulas_and_spanned_merges() {
+    let options = ConversionOptions {
+        excel_formulas: true,
+        excel_merged_cells: MergedCellMode::Span,
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_merged.xlsx", Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("| Region | Revenue |  |"));
+    assert!(text.contains("|  | Q1 | Q2 |"));
+    assert!(text.contains("| Total | 40 (`=SUM(B3:B4)`) | 60 (`=SUM(C3:C4)`) |"));
+}
+
+#[test]
+fn test_excel_tables_and_regions() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_tables.xlsx", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.starts_with("## Summary\n\n| Name | Value |"));
+    // Blank rows and columns separate data regions into their own tables.
+    assert!(text.contains("| beta | 2 |\n\n| Code | Owner | Status |"));
+    assert!(text.contains("## Data\n\n### Sales\n\n| Product | Units | Price |"));
+    assert!(text.contains("| Pad | 5 | 2 |\n\n| Note | Flag |"));
+}
+
+#[test]
+fn test_excel_range_selection() {
+    let markitdown = MarkItDown::new();
+    let convert = |range: &str| {
+        let options = ConversionOptions {
+            excel_range: Some(range.to_string()),
+            ..Default::default()
+        };
+        markitdown
+            .convert("tests/test_files/test_tables.xlsx", Some(options))
+            .unwrap()
+            .unwrap()
+            .text_content
+    };
+
+    let text = convert("Prices");
+    assert!(text.starts_with("## Data\n\n### Prices\n\n| Price |"));
+    assert!(text.ends_with("| 2 |"));
+
+    let text = convert("Summary!A5:B6");
+    assert_eq!(
+        text,
+        "## Summary\n\n| Code | Owner |\n| --- | --- |\n| X1 | Ana |"
+    );
+
+    let text = convert("sales");
+    assert!(text.contains("### Sales"));
+    assert!(!text.contains("Note"));
+
+    // A whole-sheet reference only covers the cells in use.
+    let text = convert("Summary!A1:XFD1048576");
+    assert!(text.contains("| X1 | Ana |"));
+
+    for range in [
+        "Missing",
+        "Summary!XFE1",
+        "A1048577",
+        &format!("{}1", "Z".repeat(20)),
+    ] {
+        let options = ConversionOptions {
+            excel_range: Some(range.to_string()),
+            ..Default::default()
+        };
+        let result = markitdown.convert("tests/test_files/test_tables.xlsx", Some(options));
+        assert!(matches!(result, Err(MarkitdownError::InvalidOption(_))));
+    }
+}
+
 fn write_to_file(content: &str) {
     use std::io::Write;
-    let mut file = std::fs::File::create("test.md").unwrap();
+    // Kept out of the working tree so test runs leave nothing to commit.
+    let path = std::env::temp_dir().join("markitdown-test.md");
+    let mut file = std::fs::File::create(path).unwrap();
     file.write_all(content.as_bytes()).unwrap();
 }
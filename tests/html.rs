@@ -1,4 +1,9 @@
-use markitdown::{model::ConversionOptions, MarkItDown};
+use markitdown::{
+    github::GithubConverter,
+    model::{ConversionOptions, DocumentConverter, HeadingStyle, LinkMode, MarkdownStyle},
+    wikipedia::WikipediaConverter,
+    MarkItDown,
+};
 
 #[test]
 fn test_html_conversion() {
@@ -7,6 +12,7 @@ fn test_html_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -23,6 +29,7 @@ fn test_html_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -32,3 +39,260 @@ fn test_html_bytes_conversion() {
     assert!(result.is_ok());
     assert!(result.unwrap().is_some());
 }
+
+#[test]
+fn test_html_main_content() {
+    let options = ConversionOptions {
+        file_extension: Some(".html".to_string()),
+        html_main_content: true,
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_blog.html", Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.starts_with("# Does Model and Inference Parameter Matter"));
+    assert!(text.contains("\n\n## Experiment Setup\n\n"));
+    assert!(text.contains("Large language models (LLMs) are powerful tools"));
+    assert!(text.contains("an example where high cost can easily prevent"));
+    assert!(!text.contains("Recent posts"));
+    assert!(!text.contains("Skip to main content"));
+    assert!(!text.contains("Copyright ©"));
+}
+
+#[test]
+fn test_html_strips_scripts_and_styles() {
+    let options = ConversionOptions {
+        file_extension: Some(".html".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let html = b"<html><head><style>p { color: red }</style></head><body>\
+        <script>track();</script><noscript>Enable JavaScript</noscript>\
+        <p>Visible text</p></body></html>";
+    let result = markitdown.convert_bytes(html, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("Visible text"));
+    assert!(!text.contains("track()"));
+    assert!(!text.contains("color: red"));
+    assert!(!text.contains("Enable JavaScript"));
+}
+
+#[test]
+fn test_html_rendering() {
+    let options = ConversionOptions {
+        file_extension: Some(".html".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let html = r#"<h1>Report</h1>
+        <p>Some <strong>bold</strong>, <em>italic</em> and <code>code</code>.<br>Next line</p>
+        <ul><li>One</li><li>Two<ol start="3"><li>Three</li></ol></li></ul>
+        <pre><code class="language-rust">fn main() {
+    println!("hi");
+}</code></pre>
+        <dl><dt>Term</dt><dd>Definition</dd></dl>
+        <figure><img src="chart.png" alt="Chart"><figcaption>Sales by year</figcaption></figure>
+        <table><thead><tr><th>Name</th><th align="right">Qty</th></tr></thead>
+        <tbody><tr><td>a|b</td><td>1</td></tr></tbody></table>"#;
+    let result = markitdown.convert_bytes(html.as_bytes(), Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert_eq!(
+        text,
+        "# Report\n\n\
+         Some **bold**, *italic* and `code`.  \nNext line\n\n\
+         - One\n- Two\n  3. Three\n\n\
+         ```rust\nfn main() {\n    println!(\"hi\");\n}\n```\n\n\
+         Term\n: Definition\n\n\
+         ![Chart](chart.png)\n\n*Sales by year*\n\n\
+         | Name | Qty |\n| --- | ---: |\n| a\\|b | 1 |"
+    );
+}
+
+#[test]
+fn test_html_complex_table_and_styles() {
+    let options = ConversionOptions {
+        file_extension: Some(".html".to_string()),
+        html_style: MarkdownStyle {
+            heading: HeadingStyle::Setext,
+            bullet: '*',
+            emphasis: '_',
+        },
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let html = r#"<h2>Totals</h2><ul><li><em>Item</em></li></ul>
+        <table><tr><th colspan="2">Merged</th></tr><tr><td>1</td><td>2</td></tr></table>"#;
+    let result = markitdown.convert_bytes(html.as_bytes(), Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.starts_with("Totals\n------\n\n* _Item_\n\n<table>"));
+    assert!(text.contains(r#"<th colspan="2">Merged</th>"#));
+}
+
+#[test]
+fn test_html_relative_links() {
+    let markitdown = MarkItDown::new();
+    let convert = |html: &str, url: Option<&str>, html_links: LinkMode| {
+        let options = ConversionOptions {
+            file_extension: Some(".html".to_string()),
+            url: url.map(str::to_string),
+            html_links,
+            ..Default::default()
+        };
+        markitdown
+            .convert_bytes(html.as_bytes(), Some(options))
+            .unwrap()
+            .unwrap()
+            .text_content
+    };
+
+    let html = r##"<p><a href="../guide/intro.html">Intro</a> <a href="#usage">Usage</a>
+        <img src="/img/logo.png" alt="Logo"></p>"##;
+    let text = convert(
+        html,
+        Some("https://example.com/docs/v1/index.html"),
+        LinkMode::Keep,
+    );
+    assert_eq!(
+        text,
+        "[Intro](https://example.com/docs/guide/intro.html) [Usage](#usage) \
+         ![Logo](https://example.com/img/logo.png)"
+    );
+
+    let html = r#"<head><base href="/static/"></head><p><a href="a.html">A</a></p>"#;
+    let text = convert(html, Some("https://example.com/docs/"), LinkMode::Keep);
+    assert_eq!(text, "[A](https://example.com/static/a.html)");
+
+    let text = convert(html, None, LinkMode::Strip);
+    assert_eq!(text, "A");
+}
+
+#[test]
+fn test_wikipedia_conversion() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_wikipedia.html", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(result.title.as_deref(), Some("Ferris (mascot)"));
+    let text = result.text_content;
+    assert!(text.starts_with("# Ferris (mascot)\n\n- **Created by**: Karen Rustad Tölva\n"));
+    assert!(text.contains(
+        "**Ferris** is the unofficial [mascot](https://en.wikipedia.org/wiki/Mascot \"Mascot\")"
+    ));
+    assert!(text.contains("## History"));
+    for noise in [
+        "edit",
+        "[1]",
+        "citation needed",
+        "Main page",
+        "Retrieved",
+        "Servo",
+    ] {
+        assert!(!text.contains(noise), "unexpected {:?}", noise);
+    }
+}
+
+#[test]
+fn test_site_converters() {
+    let path = "tests/test_files/test_wikipedia.html";
+    let page = MarkItDown::new().convert(path, None).unwrap().unwrap();
+
+    let article = WikipediaConverter.convert(path, None).unwrap();
+    assert_eq!(article.text_content, page.text_content);
+    assert_eq!(article.metadata, page.metadata);
+    let bytes = std::fs::read(path).unwrap();
+    let article = WikipediaConverter.convert_bytes(&bytes, None).unwrap();
+    assert_eq!(article.text_content, page.text_content);
+
+    assert!(GithubConverter.convert(path, None).is_err());
+    assert!(WikipediaConverter
+        .convert("tests/test_files/test_blog.html", None)
+        .is_err());
+}
+
+#[test]
+fn test_stackexchange_conversion() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_stackoverflow.html", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(
+        result.title.as_deref(),
+        Some("How do I convert a String into a &str?")
+    );
+    let text = result.text_content;
+    assert!(text.contains("Tags: rust, string\n\n## Accepted answer (score 325)\n\n"));
+    assert!(text.contains("```rust\nlet t: &str = s.as_str();\nlet u: &str = &s;\n```"));
+    assert!(text.ends_with(
+        "## Answer (score 12)\n\nYou can also call `&*s`, although it is less readable."
+    ));
+    assert!(!text.contains("Have you read the book?"));
+    assert!(!text.contains("Related question"));
+}
+
+#[test]
+fn test_github_readme_conversion() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_github_readme.html", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text
+        .starts_with("# uhobnil/markitdown-rs\n\nA Rust port of markitdown\n\n# markitdown-rs\n"));
+    assert!(text.contains("```rust\nuse markitdown::MarkItDown;\n```"));
+    assert!(text.contains("- [x] Excel\n- [ ] Audio"));
+    assert!(!text.contains("Sign in"));
+    assert!(!text.contains("Copy"));
+}
+
+#[test]
+fn test_html_metadata() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_blog.html", None);
+    let result = result.unwrap().unwrap();
+    let metadata = &result.metadata;
+    assert_eq!(
+        result.title.as_deref(),
+        Some("Does Model and Inference Parameter Matter in LLM Applications? - A Case Study for MATH | AutoGen")
+    );
+    assert_eq!(metadata["description"], "level 2 algebra");
+    assert_eq!(
+        metadata["canonical_url"],
+        "https://microsoft.github.io/autogen/blog/2023/04/21/LLM-tuning-math"
+    );
+    assert_eq!(metadata["language"], "en");
+    assert_eq!(
+        metadata["author"],
+        "https://www.linkedin.com/in/chi-wang-49b15b16/"
+    );
+    assert_eq!(metadata["published"], "2023-04-21T00:00:00.000Z");
+}
+
+#[test]
+fn test_html_title_parsing() {
+    let markitdown = MarkItDown::new();
+    let options = ConversionOptions {
+        file_extension: Some(".html".to_string()),
+        ..Default::default()
+    };
+
+    let html = "<html><body><svg><title>Logo</title></svg>\n\
+                <head><TITLE>\n  Pride &amp;\n  Prejudice\n</TITLE></head></body></html>";
+    let result = markitdown.convert_bytes(html.as_bytes(), Some(options.clone()));
+    let result = result.unwrap().unwrap();
+    assert_eq!(result.title.as_deref(), Some("Pride & Prejudice"));
+
+    let html =
+        "<html><body><svg><title>Logo</title></svg><h1>Chapter <em>One</em></h1></body></html>";
+    let result = markitdown.convert_bytes(html.as_bytes(), Some(options));
+    let result = result.unwrap().unwrap();
+    assert_eq!(result.title.as_deref(), Some("Chapter One"));
+    assert!(!result.metadata.contains_key("title"));
+}
@@ -1,4 +1,14 @@
-use markitdown::{model::ConversionOptions, MarkItDown};
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::TcpListener;
+use std::sync::Arc;
+use std::thread::{self, JoinHandle};
+
+use markitdown::{
+    llm::OpenAiCompatibleClient,
+    model::{ConversionOptions, OcrLine, WarningKind},
+    ocr::TesseractOcr,
+    MarkItDown,
+};
 
 #[test]
 fn test_image_conversion() {
@@ -7,6 +17,7 @@ fn test_image_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     if let Ok(_) = std::env::var("GEMINI_API_KEY") {
@@ -28,6 +39,7 @@ fn test_image_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     if std::env::var("GEMINI_API_KEY").is_ok() {
@@ -41,3 +53,363 @@ fn test_image_bytes_conversion() {
     assert!(result.is_ok());
     assert!(result.unwrap().is_some());
 }
+
+#[cfg(unix)]
+#[test]
+fn test_image_ocr() {
+    let options = ConversionOptions {
+        file_extension: Some(".jpg".to_string()),
+        ocr: true,
+        ocr_engine: Some(Arc::new(TesseractOcr {
+            command: "tests/test_files/fake_tesseract.sh".to_string(),
+            languages: "eng".to_string(),
+        })),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.jpg", Some(options));
+    let result = result.unwrap().unwrap();
+    assert!(result
+        .text_content
+        .ends_with("\n# Text:\nOpening hours:\n7am-3pm\n"));
+    assert_eq!(
+        result.ocr_lines,
+        vec![
+            OcrLine {
+                page: 1,
+                text: "Opening hours:".to_string(),
+                left: 40.0,
+                top: 30.0,
+                width: 300.0,
+                height: 24.0,
+                confidence: 92.5,
+            },
+            OcrLine {
+                page: 1,
+                text: "7am-3pm".to_string(),
+                left: 40.0,
+                top: 70.0,
+                width: 260.0,
+                height: 22.0,
+                confidence: 90.0,
+            },
+        ]
+    );
+}
+
+#[test]
+fn test_image_ocr_missing_engine() {
+    let options = ConversionOptions {
+        file_extension: Some(".jpg".to_string()),
+        ocr: true,
+        ocr_engine: Some(Arc::new(TesseractOcr {
+            command: "tests/test_files/no_such_tesseract".to_string(),
+            languages: "eng".to_string(),
+        })),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert_bytes(include_bytes!("./test_files/test.jpg"), Some(options));
+    let result = result.unwrap().unwrap();
+    assert!(result.ocr_lines.is_empty());
+    assert_eq!(result.warnings.len(), 1);
+    assert!(result.warnings[0].message.starts_with("OCR failed:"));
+}
+
+#[test]
+fn test_png_metadata() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.png", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(
+        result.text_content,
+        "- Format: PNG\n\
+         - Dimensions: 4x3\n\
+         - Color: RGBA, 8 bits per channel\n\
+         - Date taken: 2024-05-01 09:30:00\n\
+         - Camera: Acme Field Camera 2\n\
+         - Title: Terraces at dawn\n\
+         - Keywords: coffee, farm & field\n\
+         - Artist: Ana Ruiz, Tom Berg\n"
+    );
+    assert_eq!(result.metadata["width"], "4");
+    assert_eq!(result.metadata["height"], "3");
+}
+
+#[test]
+fn test_animation_frames() {
+    let markitdown = MarkItDown::new();
+
+    let gif = markitdown.convert("tests/test_files/test_animated.gif", None);
+    let gif = gif.unwrap().unwrap();
+    assert_eq!(
+        gif.text_content,
+        "- Format: GIF\n\
+         - Dimensions: 5x4\n\
+         - Color: Indexed, 2 bits per pixel\n\
+         - Frames: 3\n"
+    );
+
+    let webp = markitdown.convert_bytes(
+        include_bytes!("./test_files/test_animated.webp"),
+        Some(ConversionOptions {
+            file_extension: Some(".webp".to_string()),
+            ..Default::default()
+        }),
+    );
+    let webp = webp.unwrap().unwrap();
+    assert!(webp.text_content.starts_with(
+        "- Format: WebP\n\
+         - Dimensions: 6x5\n\
+         - Color: RGBA, 8 bits per channel\n\
+         - Frames: 4\n"
+    ));
+    assert!(webp.text_content.contains("- Title: Terraces at dawn\n"));
+    assert_eq!(webp.metadata["frames"], "4");
+}
+
+#[test]
+fn test_tiff_and_heic_metadata() {
+    let markitdown = MarkItDown::new();
+
+    let tiff = markitdown.convert("tests/test_files/test.tiff", None);
+    let tiff = tiff.unwrap().unwrap();
+    assert!(tiff
+        .text_content
+        .starts_with("- Format: TIFF\n- Dimensions: 3x2\n- Color: RGB, 8 bits per channel\n"));
+    assert!(tiff.text_content.contains("- Camera: Acme\n"));
+    assert!(tiff.text_content.contains("- Artist: Ana Ruiz, Tom Berg\n"));
+
+    // The size of the primary image rather than of its thumbnail.
+    let heic = markitdown.convert_bytes(
+        include_bytes!("./test_files/test.heic"),
+        Some(ConversionOptions {
+            file_extension: Some(".heic".to_string()),
+            ..Default::default()
+        }),
+    );
+    let heic = heic.unwrap().unwrap();
+    assert!(heic.text_content.starts_with(
+        "- Format: HEIC\n- Dimensions: 4032x3024\n- Color: RGB, 8 bits per channel\n"
+    ));
+    assert!(heic
+        .text_content
+        .contains("- Camera: Acme Field Camera 2\n"));
+}
+
+#[test]
+fn test_tiff_entry_count_overflow() {
+    // A LONG ImageWidth entry whose count overflows the size of its values.
+    let mut tiff = b"II*\0\x08\0\0\0\x01\0".to_vec();
+    tiff.extend_from_slice(&[0x00, 0x01, 0x04, 0x00, 0x01, 0x00, 0x00, 0x40]);
+    tiff.extend_from_slice(&[0; 8]);
+    let options = ConversionOptions {
+        file_extension: Some(".tiff".to_string()),
+        ..Default::default()
+    };
+
+    let result = MarkItDown::new().convert_bytes(&tiff, Some(options));
+    assert!(result.unwrap().is_none());
+}
+
+#[test]
+fn test_jpeg_extension() {
+    let options = ConversionOptions {
+        file_extension: Some(".jpeg".to_string()),
+        ..Default::default()
+    };
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.jpg", Some(options));
+    let result = result.unwrap().unwrap();
+    assert!(result
+        .text_content
+        .starts_with("- Format: JPEG\n- Dimensions: 1615x1967\n"));
+}
+
+#[test]
+fn test_photo_metadata() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_metadata.jpg", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(
+        result.text_content,
+        "- Format: JPEG\n\
+         - Dimensions: 6000x4000\n\
+         - Color: RGB, 8 bits per channel\n\
+         - Date taken: 2023-09-14 07:12:45\n\
+         - Camera: Canon EOS R6\n\
+         - Lens: RF 35mm F1.8 MACRO IS STM\n\
+         - Exposure: 1/250 s, f/2.8, ISO 200, 35 mm\n\
+         - GPS: 4.595883, -74.070100\n\
+         - Altitude: 1825.5 m\n\
+         - Title: Cotopaxi sunrise\n\
+         - Description: First light on the summit, seen from the páramo.\n\
+         - Keywords: volcano, Ecuador\n\
+         - Artist: Ana Ruiz\n\
+         - Copyright: CC BY 4.0\n"
+    );
+    assert_eq!(result.metadata["gps"], "4.595883, -74.070100");
+
+    let full = markitdown.convert(
+        "tests/test_files/test_metadata.jpg",
+        Some(ConversionOptions {
+            image_full_metadata: true,
+            ..Default::default()
+        }),
+    );
+    let full = full.unwrap().unwrap();
+    assert!(full.text_content.starts_with(&result.text_content));
+    assert!(full
+        .text_content
+        .contains("\n## EXIF\n\n- Make: \"Canon\"\n"));
+    assert!(full.text_content.contains("- MakerNote: 0x43616e6f6e00"));
+    assert!(full
+        .text_content
+        .contains("\n## IPTC\n\n- Object Name: Cotopaxi sunrise\n"));
+}
+
+/// Serves canned chat completion responses on a local port, one per request,
+/// and hands back the requests with lowercased headers.
+fn mock_chat_server(responses: Vec<(u16, String)>) -> (String, JoinHandle<Vec<String>>) {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let base_url = format!("http://{}/v1", listener.local_addr().unwrap());
+    let handle = thread::spawn(move || {
+        let mut requests = Vec::new();
+        for (status, body) in responses {
+            let (stream, _) = listener.accept().unwrap();
+            let mut reader = BufReader::new(stream);
+            let mut request = String::new();
+            let mut length = 0;
+            loop {
+                let mut line = String::new();
+                reader.read_line(&mut line).unwrap();
+                let line = line.to_lowercase();
+                if let Some(value) = line.strip_prefix("content-length:") {
+                    length = value.trim().parse().unwrap();
+                }
+                request.push_str(&line);
+                if line == "\r\n" {
+                    break;
+                }
+            }
+            let mut content = vec![0; length];
+            reader.read_exact(&mut content).unwrap();
+            request.push_str(&String::from_utf8(content).unwrap());
+            requests.push(request);
+
+            write!(
+                reader.get_mut(),
+                "HTTP/1.1 {} Mock\r\nContent-Type: application/json\r\nContent-Length: {}\r\nConnection: close\r\n\r\n{}",
+                status,
+                body.len(),
+                body
+            )
+            .unwrap();
+        }
+        requests
+    });
+    (base_url, handle)
+}
+
+#[test]
+fn test_image_caption_with_compatible_client() {
+    let caption = r#"{"choices":[{"message":{"role":"assistant","content":" Rows of coffee plants on a hillside. "}}]}"#;
+    let (base_url, server) =
+        mock_chat_server(vec![(200, caption.to_string()), (200, caption.to_string())]);
+    let client = OpenAiCompatibleClient {
+        api_key: Some("test-key".to_string()),
+        headers: vec![("X-Team".to_string(), "vision".to_string())],
+        ..OpenAiCompatibleClient::new(&base_url, "llava")
+    };
+    let options = ConversionOptions {
+        llm: Some(Arc::new(client)),
+        ..Default::default()
+    };
+    let markitdown = MarkItDown::new();
+
+    let from_path = markitdown.convert("tests/test_files/test.png", Some(options.clone()));
+    let from_path = from_path.unwrap().unwrap();
+    let from_bytes =
+        markitdown.convert_bytes(include_bytes!("./test_files/test.png"), Some(options));
+    let from_bytes = from_bytes.unwrap().unwrap();
+    assert!(from_path
+        .text_content
+        .ends_with("\n# Description:\nRows of coffee plants on a hillside."));
+    assert_eq!(from_path.text_content, from_bytes.text_content);
+
+    let requests = server.join().unwrap();
+    assert_eq!(requests[0], requests[1]);
+    assert!(requests[0].starts_with("post /v1/chat/completions http/1.1\r\n"));
+    assert!(requests[0].contains("authorization: bearer test-key\r\n"));
+    assert!(requests[0].contains("x-team: vision\r\n"));
+    assert!(requests[0].contains(r#""model":"llava""#));
+    assert!(requests[0].contains(r#""url":"data:image/png;base64,iVBORw0KGgo"#));
+}
+
+#[test]
+fn test_image_caption_error() {
+    let error = r#"{"error":{"message":"model not found"}}"#;
+    let (base_url, server) = mock_chat_server(vec![(404, error.to_string())]);
+    let options = ConversionOptions {
+        llm: Some(Arc::new(OpenAiCompatibleClient::new(&base_url, "missing"))),
+        ..Default::default()
+    };
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.png", Some(options));
+    let result = result.unwrap().unwrap();
+    server.join().unwrap();
+    assert!(!result.text_content.contains("# Description:"));
+    assert_eq!(result.warnings.len(), 1);
+    assert_eq!(result.warnings[0].kind, WarningKind::Llm);
+    assert!(result.warnings[0].message.contains("model not found"));
+}
+
+#[test]
+fn test_image_caption_prompt() {
+    let caption = r#"{"choices":[{"message":{"role":"assistant","content":"A coffee farm."}}]}"#;
+    let (base_url, server) = mock_chat_server(vec![(200, caption.to_string())]);
+    let options = ConversionOptions {
+        llm: Some(Arc::new(OpenAiCompatibleClient::new(&base_url, "llava"))),
+        llm_prompt: Some(
+            "Write alt text for {file_name} ({mime_type}) near: {context} {unknown}".to_string(),
+        ),
+        llm_preamble: Some("You write alt text.".to_string()),
+        llm_temperature: Some(0.0),
+        llm_max_tokens: Some(60),
+        llm_params: Some(serde_json::json!({ "top_p": 0.9, "seed": 7 })),
+        llm_context: Some("Figure 2: harvest".to_string()),
+        ..Default::default()
+    };
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.png", Some(options));
+    assert!(result
+        .unwrap()
+        .unwrap()
+        .text_content
+        .ends_with("\n# Description:\nA coffee farm."));
+
+    let requests = server.join().unwrap();
+    let (_, body) = requests[0].split_once("\r\n\r\n").unwrap();
+    let body: serde_json::Value = serde_json::from_str(body).unwrap();
+    assert_eq!(body["messages"][0]["content"], "You write alt text.");
+    assert_eq!(
+        body["messages"][1]["content"][0]["text"],
+        "Write alt text for test.png (image/png) near: Figure 2: harvest {unknown}"
+    );
+    assert_eq!(body["temperature"], 0.0);
+    assert_eq!(body["max_tokens"], 60);
+    assert_eq!(body["top_p"], 0.9);
+    assert_eq!(body["seed"], 7);
+    // Gemini's generation settings stay out of OpenAI-style requests.
+    assert!(body.get("top_k").is_none());
+    assert!(body.get("candidate_count").is_none());
+}
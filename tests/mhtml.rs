@@ -0,0 +1,91 @@
+use markitdown::{
+    mhtml::MhtmlConverter, model::ConversionOptions, model::DocumentConverter, MarkItDown,
+};
+
+#[test]
+fn test_mhtml_conversion() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.mhtml", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(result.title.as_deref(), Some("Café notes — Saved page"));
+    let text = result.text_content;
+    assert!(text.starts_with("# Café notes\n\nBrewing ratios for a long, flowing paragraph"));
+    assert!(text.contains("far longer than seventy-six characters."));
+    assert!(text.contains("![Espresso shot](https://example.com/coffee/espresso.png)"));
+    assert!(text.contains("[grind guide](https://example.com/guides/grind.html)"));
+    assert!(text.ends_with("Embedded frame: 18 g in, 36 g out."));
+}
+
+#[test]
+fn test_mhtml_bytes_conversion() {
+    let bytes = std::fs::read("tests/test_files/test.mhtml").unwrap();
+    let options = ConversionOptions {
+        file_extension: Some(".mhtml".to_string()),
+        ..Default::default()
+    };
+
+    let from_bytes = MhtmlConverter.convert_bytes(&bytes, Some(options.clone()));
+    let from_path = MhtmlConverter.convert("tests/test_files/test.mhtml", Some(options));
+    assert_eq!(
+        from_bytes.unwrap().text_content,
+        from_path.unwrap().text_content
+    );
+}
+
+#[test]
+fn test_mhtml_single_part() {
+    let archive = "Subject: =?utf-8?B?UmVzdW3DqQ==?=\r\n\
+                   MIME-Version: 1.0\r\n\
+                   Content-Type: text/html; charset=\"iso-8859-1\"\r\n\
+                   Content-Transfer-Encoding: quoted-printable\r\n\
+                   \r\n\
+                   <p>Na=EFve =\r\ncaf=E9</p>\r\n";
+    let options = ConversionOptions {
+        file_extension: Some(".mht".to_string()),
+        ..Default::default()
+    };
+
+    let result = MhtmlConverter.convert_bytes(archive.as_bytes(), Some(options));
+    let result = result.unwrap();
+    assert_eq!(result.title.as_deref(), Some("Resumé"));
+    assert_eq!(result.text_content, "Naïve café");
+}
+
+#[test]
+fn test_mhtml_rejects_other_files() {
+    let options = ConversionOptions {
+        file_extension: Some(".html".to_string()),
+        ..Default::default()
+    };
+    let result = MhtmlConverter.convert("tests/test_files/test_blog.html", Some(options));
+    assert!(result.is_err());
+
+    let result = MhtmlConverter.convert_bytes(b"<html><body>Hi</body></html>", None);
+    assert!(result.is_err());
+}
+
+#[test]
+fn test_mhtml_site_converter() {
+    let html = std::fs::read_to_string("tests/test_files/test_wikipedia.html").unwrap();
+    let archive = format!(
+        "MIME-Version: 1.0\r\n\
+         Content-Type: text/html; charset=\"utf-8\"\r\n\
+         Content-Location: https://en.wikipedia.org/wiki/Ferris_(mascot)\r\n\
+         \r\n\
+         {}",
+        html
+    );
+    let options = ConversionOptions {
+        file_extension: Some(".mht".to_string()),
+        ..Default::default()
+    };
+    let markitdown = MarkItDown::new();
+
+    let saved = markitdown.convert_bytes(archive.as_bytes(), Some(options));
+    let saved = saved.unwrap().unwrap();
+    let page = markitdown.convert("tests/test_files/test_wikipedia.html", None);
+    let page = page.unwrap().unwrap();
+    assert_eq!(saved.title, page.title);
+    assert_eq!(saved.text_content, page.text_content);
+}
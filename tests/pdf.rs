@@ -1,4 +1,12 @@
-use markitdown::{model::ConversionOptions, MarkItDown};
+use std::sync::{Arc, Mutex};
+
+use markitdown::{
+    error::MarkitdownError,
+    model::{ConversionOptions, DocumentConverter, FormField, FormFieldKind, OcrLine},
+    ocr::OcrEngine,
+    pdf::PdfConverter,
+    MarkItDown,
+};
 
 #[test]
 fn test_pdf_conversion() {
@@ -7,6 +15,7 @@ fn test_pdf_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -23,6 +32,7 @@ fn test_pdf_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -31,3 +41,337 @@ fn test_pdf_bytes_conversion() {
     assert!(result.is_ok());
     assert!(result.unwrap().is_some());
 }
+
+#[test]
+fn test_pdf_page_markers() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.pdf", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(result.pages.len(), 12);
+    assert_eq!(result.pages[0].number, 1);
+    assert!(result.pages[0].text.starts_with("[arXiv:1604.00857v2"));
+    assert!(result
+        .text_content
+        .contains("\n\n<!-- Page 1 -->\n\n[arXiv:1604.00857v2"));
+    assert!(result.text_content.contains("\n\n<!-- Page 12 -->\n\n"));
+}
+
+#[test]
+fn test_pdf_page_selection() {
+    let options = ConversionOptions {
+        file_extension: Some(".pdf".to_string()),
+        pdf_pages: Some("2-3, 11-".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test.pdf", Some(options.clone()));
+    let result = result.unwrap().unwrap();
+    let numbers: Vec<u32> = result.pages.iter().map(|page| page.number).collect();
+    assert_eq!(numbers, vec![2, 3, 11, 12]);
+    assert!(result
+        .text_content
+        .starts_with("## Contents\n\n- VI Summary and conclusions (page 11)\n"));
+    assert!(result.text_content.contains("\n\n<!-- Page 2 -->\n\n"));
+    assert!(!result.text_content.contains("<!-- Page 1 -->"));
+
+    // test.pdf has 12 pages.
+    for pages in ["5-2", "13", "50-60", "2, 13-"] {
+        let options = ConversionOptions {
+            pdf_pages: Some(pages.to_string()),
+            ..options.clone()
+        };
+        let result = markitdown.convert("tests/test_files/test.pdf", Some(options));
+        assert!(matches!(result, Err(MarkitdownError::InvalidOption(_))));
+    }
+}
+
+#[test]
+fn test_pdf_layout() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_layout.pdf", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert_eq!(
+        text,
+        "<!-- Page 1 -->\n\n\
+         # Coffee Market Review\n\n\
+         ## Introduction\n\n\
+         Our members harvested more coffee than in any previous season, thanks to the \
+         sustainable farming programme launched in 2023 and to favourable rainfall in the \
+         spring.\n\n\
+         Cooperative membership grew to 412 farms across three regions, with most of the new \
+         members joining from the northern valleys.\n\n\
+         ## Prices\n\n\
+         Green coffee prices stayed above the five year average for most of the year, while \
+         export volumes rose by eleven percent.\n\n\
+         <!-- Page 2 -->\n\n\
+         ## Outlook\n\n\
+         We expect another strong harvest next year and will invest in new drying beds for the \
+         smaller farms."
+    );
+}
+
+#[test]
+fn test_pdf_tables() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_tables.pdf", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert_eq!(
+        text,
+        "<!-- Page 1 -->\n\n\
+         Harvest by region for the last season.\n\n\
+         | Region | Farms | Tonnes |\n\
+         | --- | --- | --- |\n\
+         | North | 180 | 2,400 |\n\
+         | South | 132 | 1,950 |\n\
+         | East | 100 | 1,210 |\n\n\
+         Prices paid to members, without ruling lines.\n\n\
+         | Grade | Price | Change |\n\
+         | --- | --- | --- |\n\
+         | AA | 4.10 | +8% |\n\
+         | AB | 3.75 | +5% |\n\
+         | C | 2.90 | -2% |"
+    );
+
+    let options = ConversionOptions {
+        file_extension: Some(".pdf".to_string()),
+        pdf_skip_tables: true,
+        ..Default::default()
+    };
+    let result = PdfConverter.convert("tests/test_files/test_tables.pdf", Some(options));
+    let text = result.unwrap().text_content;
+    assert!(!text.contains('|'));
+    assert!(text.contains("Region Farms Tonnes"));
+}
+
+#[test]
+fn test_pdf_outline_and_links() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_outline.pdf", None);
+    let result = result.unwrap().unwrap();
+    assert_eq!(
+        result.text_content,
+        "## Contents\n\n\
+         - Planting (page 1)\n  - Seedlings (page 1)\n- Harvest (page 2)\n\n\
+         <!-- Page 1 -->\n\n\
+         # Field Guide\n\n\
+         ## 1 Planting\n\n\
+         ### Seedlings\n\n\
+         Seedlings are raised in shaded nurseries for six months and planted out at the start \
+         of the rains; see the [cooperative website](https://example.org/calendar) for the \
+         planting calendar.\n\n\
+         <!-- Page 2 -->\n\n\
+         ## 2 Harvest\n\n\
+         Cherries are picked by hand when they turn deep red, and are pulped on the day they \
+         are picked."
+    );
+    assert!(result.pages[1].text.starts_with("## 2 Harvest"));
+}
+
+#[test]
+fn test_pdf_annotations() {
+    let options = ConversionOptions {
+        file_extension: Some(".pdf".to_string()),
+        pdf_annotations: true,
+        pdf_pages: Some("2".to_string()),
+        ..Default::default()
+    };
+    let result = PdfConverter.convert("tests/test_files/test_outline.pdf", Some(options));
+    let text = result.unwrap().text_content;
+    assert!(text.starts_with("## Contents\n\n- Harvest (page 2)\n\n<!-- Page 2 -->"));
+    assert!(text.ends_with(
+        "are picked.\n\n\
+         > **Highlight (Maria):** \"hand\"\n\
+         > Machines bruise the cherries.\n\n\
+         > **Note (Maria):** Check these dates with the regional office."
+    ));
+}
+
+#[test]
+fn test_pdf_password() {
+    let markitdown = MarkItDown::new();
+    let path = "tests/test_files/test_encrypted_rc4.pdf";
+
+    let result = markitdown.convert(path, None);
+    assert!(matches!(result, Err(MarkitdownError::PasswordRequired(_))));
+
+    let options = |password: &str| {
+        Some(ConversionOptions {
+            password: Some(password.to_string()),
+            ..Default::default()
+        })
+    };
+    let result = markitdown.convert(path, options("espresso"));
+    assert!(matches!(result, Err(MarkitdownError::WrongPassword(_))));
+
+    // Both the user and the owner password open the document.
+    for password in ["coffee", "roaster"] {
+        let result = markitdown.convert(path, options(password));
+        let text = result.unwrap().unwrap().text_content;
+        assert!(text.contains("Revenue grew by twelve percent over the previous quarter."));
+    }
+}
+
+#[test]
+fn test_pdf_unsupported_encryption() {
+    let markitdown = MarkItDown::new();
+    let bytes = std::fs::read("tests/test_files/test_encrypted_rc4.pdf").unwrap();
+    let edit = |from: &str, to: &str| {
+        let at = bytes
+            .windows(from.len())
+            .position(|window| window == from.as_bytes())
+            .unwrap();
+        let mut edited = bytes.clone();
+        edited[at..at + to.len()].copy_from_slice(to.as_bytes());
+        edited
+    };
+    let options = Some(ConversionOptions {
+        file_extension: Some(".pdf".to_string()),
+        ..Default::default()
+    });
+
+    // Another security handler, then a revision past the known ones, both
+    // kept the same length so the cross-reference table stays valid.
+    for edited in [
+        edit("/Filter /Standard", "/Filter /Custom__"),
+        edit("/R 3", "/R 9"),
+    ] {
+        let result = markitdown.convert_bytes(&edited, options.clone());
+        assert!(matches!(
+            result,
+            Err(MarkitdownError::UnsupportedEncryption(_))
+        ));
+    }
+}
+
+#[test]
+fn test_pdf_aes_encryption() {
+    let markitdown = MarkItDown::new();
+
+    // Only an owner password is set, so no password is needed to read it.
+    let result = markitdown.convert("tests/test_files/test_encrypted_aes128.pdf", None);
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("Revenue grew by twelve percent over the previous quarter."));
+
+    // AES-256 with the objects packed into an encrypted object stream.
+    let bytes = std::fs::read("tests/test_files/test_encrypted_aes256.pdf").unwrap();
+    let options = ConversionOptions {
+        file_extension: Some(".pdf".to_string()),
+        password: Some("coffee".to_string()),
+        ..Default::default()
+    };
+    let result = PdfConverter.convert_bytes(&bytes, Some(options));
+    assert_eq!(
+        result.unwrap().text_content,
+        "## Contents\n\n\
+         - Quarterly Results (page 1)\n\n\
+         <!-- Page 1 -->\n\n\
+         ## Quarterly Results\n\n\
+         Revenue grew by twelve percent over the previous quarter."
+    );
+}
+
+#[test]
+fn test_pdf_form_fields() {
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_form.pdf", None);
+    let result = result.unwrap().unwrap();
+    assert!(result.text_content.ends_with(
+        "## Form Fields\n\n\
+         | Field | Type | Value |\n\
+         | --- | --- | --- |\n\
+         | applicant.name | Text | Maria Lopez |\n\
+         | applicant.email | Text | maria@example.org |\n\
+         | member | Checkbox | Checked |\n\
+         | region | Radio button | North |\n\
+         | grade | Combo box | AA |\n\
+         | notes | Text | Picked early<br>Dried on raised beds |\n\
+         | organic | Checkbox | Unchecked |\n\
+         | signature | Signature |  |"
+    ));
+
+    // Push buttons hold no value.
+    assert_eq!(result.form_fields.len(), 8);
+    assert_eq!(
+        result.form_fields[2],
+        FormField {
+            name: "member".to_string(),
+            kind: FormFieldKind::Checkbox,
+            value: Some("Yes".to_string()),
+            checked: Some(true),
+        }
+    );
+    assert_eq!(result.form_fields[6].checked, Some(false));
+    assert_eq!(result.form_fields[7].kind, FormFieldKind::Signature);
+}
+
+/// Stands in for an OCR engine, answering every image with the same lines.
+#[derive(Debug, Default)]
+struct FakeOcr {
+    images: Mutex<Vec<Vec<u8>>>,
+}
+
+impl OcrEngine for FakeOcr {
+    fn recognize(&self, image: &[u8]) -> Result<Vec<OcrLine>, MarkitdownError> {
+        self.images.lock().unwrap().push(image.to_vec());
+        let line = |text: &str, top: f64, width: f64| OcrLine {
+            page: 1,
+            text: text.to_string(),
+            left: 10.0,
+            top,
+            width,
+            height: 6.0,
+            confidence: 91.5,
+        };
+        Ok(vec![
+            line("Harvest report for the", 10.0, 180.0),
+            line("northern terraces.", 18.0, 140.0),
+        ])
+    }
+}
+
+#[test]
+fn test_pdf_ocr() {
+    let engine = Arc::new(FakeOcr::default());
+    let options = ConversionOptions {
+        ocr_engine: Some(engine.clone()),
+        ..Default::default()
+    };
+    let markitdown = MarkItDown::new();
+
+    let result = markitdown.convert("tests/test_files/test_scanned.pdf", Some(options));
+    let result = result.unwrap().unwrap();
+    // Only the page without a text layer is recognised.
+    let images = engine.images.lock().unwrap();
+    assert_eq!(images.len(), 1);
+    assert!(images[0].starts_with(b"P5\n200 100\n255\n"));
+    assert_eq!(
+        result.text_content,
+        "<!-- Page 1 -->\n\n\
+         Harvest report for the northern terraces.\n\n\
+         <!-- Page 2 -->\n\n\
+         This page has a text layer."
+    );
+
+    // The image is drawn at twice its size, 100 points from the left and
+    // 92 from the top.
+    assert_eq!(result.ocr_lines.len(), 2);
+    assert_eq!(
+        result.ocr_lines[1],
+        OcrLine {
+            page: 1,
+            text: "northern terraces.".to_string(),
+            left: 120.0,
+            top: 128.0,
+            width: 280.0,
+            height: 12.0,
+            confidence: 91.5,
+        }
+    );
+}
@@ -7,6 +7,7 @@ fn test_rss_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -23,6 +24,7 @@ fn test_rss_bytes_conversion() {
         url: None,
         llm_client: None,
         llm_model: None,
+        ..Default::default()
     };
 
     let markitdown = MarkItDown::new();
@@ -31,3 +33,22 @@ fn test_rss_bytes_conversion() {
     assert!(result.is_ok());
     assert!(result.unwrap().is_some());
 }
+
+#[test]
+fn test_rss_relative_links() {
+    let options = ConversionOptions {
+        file_extension: Some(".rss".to_string()),
+        ..Default::default()
+    };
+
+    let markitdown = MarkItDown::new();
+
+    let rss = br#"<?xml version="1.0"?>
+<rss version="2.0"><channel><title>News</title><link>https://news.example.com/</link>
+<item><title>Launch</title><link>https://news.example.com/2024/launch</link>
+<description>&lt;p&gt;Read &lt;a href="notes.html"&gt;the notes&lt;/a&gt;&lt;/p&gt;</description></item>
+</channel></rss>"#;
+    let result = markitdown.convert_bytes(rss, Some(options));
+    let text = result.unwrap().unwrap().text_content;
+    assert!(text.contains("Read [the notes](https://news.example.com/2024/notes.html)"));
+}
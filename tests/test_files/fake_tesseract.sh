@@ -0,0 +1,10 @@
+#!/bin/sh
+# Stands in for tesseract in tests: drains the image and prints fixed TSV.
+cat > /dev/null
+printf 'level\tpage_num\tblock_num\tpar_num\tline_num\tword_num\tleft\ttop\twidth\theight\tconf\ttext\n'
+printf '1\t1\t0\t0\t0\t0\t0\t0\t640\t480\t-1\t\n'
+printf '4\t1\t1\t1\t1\t0\t40\t30\t300\t24\t-1\t\n'
+printf '5\t1\t1\t1\t1\t1\t40\t32\t120\t22\t96.5\tOpening\n'
+printf '5\t1\t1\t1\t1\t2\t172\t30\t168\t24\t88.5\thours:\n'
+printf '5\t1\t1\t1\t2\t1\t40\t70\t260\t22\t90\t7am-3pm\n'
+printf '5\t1\t1\t1\t2\t2\t310\t70\t10\t22\t12\t \n'